@@ -1,25 +1,63 @@
-var cacheName = 'egui-template-pwa';
+// the version and file list are filled in by sw_manifest.sh after each Trunk build,
+// a build with changed files gets a new cache and the previous one is deleted once it took over
+var version = '@VERSION@';
+var cacheName = 'rzrtiles-' + version;
 var filesToCache = [
   './',
-  './index.html',
-  './eframe_template.js',
-  './eframe_template_bg.wasm',
+  // @FILES@
 ];
 
-/* Start the service worker and cache all of the app's content */
+/* Cache all of the app's content, the new version waits until the page lets it take over */
 self.addEventListener('install', function (e) {
   e.waitUntil(
     caches.open(cacheName).then(function (cache) {
-      return cache.addAll(filesToCache);
+      return cache.addAll(filesToCache.map(function (file) {
+        return new Request(file, { cache: 'reload' });
+      }));
     })
   );
 });
 
-/* Serve cached content when offline */
+/* Drop the caches of previous versions */
+self.addEventListener('activate', function (e) {
+  e.waitUntil(
+    caches.keys().then(function (keys) {
+      return Promise.all(keys.filter(function (key) {
+        return key !== cacheName;
+      }).map(function (key) {
+        return caches.delete(key);
+      }));
+    }).then(function () {
+      return self.clients.claim();
+    })
+  );
+});
+
+/* Sent by the page when "Reload" is clicked on the update notice */
+self.addEventListener('message', function (e) {
+  if (e.data === 'skipWaiting') {
+    self.skipWaiting();
+  }
+});
+
+/* Serve cached content when offline, pages load index.html whatever their query */
 self.addEventListener('fetch', function (e) {
+  if (e.request.method !== 'GET') {
+    return;
+  }
   e.respondWith(
-    caches.match(e.request).then(function (response) {
-      return response || fetch(e.request);
+    caches.open(cacheName).then(function (cache) {
+      return cache.match(e.request, { ignoreSearch: true }).then(function (response) {
+        if (response) {
+          return response;
+        }
+        return fetch(e.request).catch(function (err) {
+          if (e.request.mode === 'navigate') {
+            return cache.match('./');
+          }
+          throw err;
+        });
+      });
     })
   );
 });
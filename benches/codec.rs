@@ -0,0 +1,76 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rzrtiles::codec::{self, scalar, TileMode};
+use rzrtiles::compress;
+
+// 32x32 tiles, the largest sheet the editor allows
+const TILES: u32 = 32;
+
+fn sheet() -> Vec<u8>
+{
+    (0..TILES * 8 * TILES * 8).map(|i| (i * 7 % 13) as u8 & 0b11).collect()
+}
+
+fn encode(c: &mut Criterion)
+{
+    let pixels = sheet();
+    let (w, h) = (TILES * 8, TILES * 8);
+    let mut out = vec![0; (w * h / 4) as usize];
+
+    let mut group = c.benchmark_group("encode 32x32 tiles");
+    group.bench_function("pixels_to_gb_tiles", |b| {
+        b.iter(|| codec::pixels_to_gb_tiles(black_box(&pixels), w, h, TileMode::Y8))
+    });
+    group.bench_function("encode_into", |b| {
+        b.iter(|| codec::encode_into(black_box(&pixels), w, h, TileMode::Y8, &mut out))
+    });
+    group.finish();
+}
+
+fn decode(c: &mut Criterion)
+{
+    let tiles = codec::pixels_to_gb_tiles(&sheet(), TILES * 8, TILES * 8, TileMode::Y8);
+    let mut out = vec![0; (TILES * 8 * TILES * 8) as usize];
+
+    let mut group = c.benchmark_group("decode 32x32 tiles");
+    group.bench_function("gb_tiles_to_pixels", |b| {
+        b.iter(|| codec::gb_tiles_to_pixels(black_box(&tiles), TILES, TILES, TileMode::Y8))
+    });
+    group.bench_function("decode_into", |b| {
+        b.iter(|| codec::decode_into(black_box(&tiles), TILES, TILES, TileMode::Y8, &mut out))
+    });
+    group.finish();
+}
+
+fn rows(c: &mut Criterion)
+{
+    let pixels = sheet();
+    let tiles = codec::pixels_to_gb_tiles(&pixels, TILES * 8, TILES * 8, TileMode::Y8);
+    let mut out = [0u8; 8];
+
+    let mut group = c.benchmark_group("rows of 32x32 tiles");
+    group.bench_function("scalar::encode_row", |b| {
+        b.iter(|| pixels.chunks_exact(8).map(|row| scalar::encode_row(black_box(row)).0 as u32).sum::<u32>())
+    });
+    group.bench_function("encode_row", |b| {
+        b.iter(|| pixels.chunks_exact(8).map(|row| codec::encode_row(black_box(row)).0 as u32).sum::<u32>())
+    });
+    group.bench_function("scalar::decode_row", |b| {
+        b.iter(|| tiles.chunks_exact(2).for_each(|row| scalar::decode_row(black_box(row[0]), row[1], &mut out)))
+    });
+    group.bench_function("decode_row", |b| {
+        b.iter(|| tiles.chunks_exact(2).for_each(|row| codec::decode_row(black_box(row[0]), row[1], &mut out)))
+    });
+    group.finish();
+}
+
+fn lz77(c: &mut Criterion)
+{
+    let tiles = codec::pixels_to_gb_tiles(&sheet(), TILES * 8, TILES * 8, TileMode::Y8);
+
+    c.bench_function("lz77_compress 32x32 tiles", |b| {
+        b.iter(|| compress::lz77_compress(black_box(&tiles)))
+    });
+}
+
+criterion_group!(benches, encode, decode, rows, lz77);
+criterion_main!(benches);
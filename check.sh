@@ -4,6 +4,7 @@ set -eux
 
 cargo check --workspace --all-targets
 cargo check --workspace --all-features --lib --target wasm32-unknown-unknown
+cargo check --workspace --no-default-features --lib
 cargo fmt --all -- --check
 cargo clippy --workspace --all-targets --all-features --  -D warnings -W clippy::all
 cargo test --workspace --all-targets --all-features
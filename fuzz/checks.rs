@@ -0,0 +1,64 @@
+// Properties shared by the fuzz targets and tests/fuzz_regressions.rs
+
+#![allow(dead_code)]
+
+use rzrtiles::codec::{self, TileMode};
+use rzrtiles::format::TileFormat;
+use rzrtiles::tl;
+
+/// whatever loads must save and load again unchanged
+pub fn tl_loader(data: &[u8])
+{
+    if let Ok((sheet, format)) = tl::read_tl(data) {
+        let saved = tl::to_tl_as(&sheet, format).expect("loaded sheet must be saveable");
+        assert_eq!(tl::read_tl(&saved), Ok((sheet, format)));
+    }
+}
+
+/// first byte selects the mode and tile format, the next two the sheet size in tiles,
+/// independent of the length of the tile data that follows. The checked functions fail
+/// exactly when the sizes do not match, and whatever decodes encodes back to the same data
+pub fn tile_decoder(data: &[u8])
+{
+    if data.len() < 3 {
+        return;
+    }
+    let mode = if data[0] & 1 == 0 { TileMode::Y8 } else { TileMode::Y16 };
+    let format = TileFormat::ALL[(data[0] >> 1) as usize % TileFormat::ALL.len()];
+    let (w, h) = (data[1] as u32, data[2] as u32);
+    let tiles = &data[3..];
+    let bytes_per_tile = format.bytes_per_tile(mode);
+
+    // sized for the tiles in the data rather than for w x h
+    let mut pixels = vec![0xff; tiles.len() / bytes_per_tile * 8 * mode as usize];
+    let fits = tiles.len() == (w * h) as usize * bytes_per_tile;
+    let decoded = format.try_decode_into(tiles, w, h, mode, &mut pixels);
+    assert_eq!(decoded.is_ok(), fits, "{:?}", decoded);
+    if format == TileFormat::Gb {
+        let mut out = vec![0xff; pixels.len()];
+        assert_eq!(codec::try_decode_into(tiles, w, h, mode, &mut out), decoded);
+        if fits {
+            assert_eq!(out, pixels);
+        }
+    }
+
+    // the same size bytes taken as pixel dimensions, mostly not whole tiles
+    let mut encoded = vec![0; tiles.len()];
+    let whole = w % 8 == 0 && h % mode as u32 == 0
+        && pixels.len() == (w * h) as usize
+        && tiles.len() == (w / 8 * h / mode as u32) as usize * bytes_per_tile;
+    assert_eq!(format.try_encode_into(&pixels, w, h, mode, &mut encoded).is_ok(), whole);
+
+    if !fits {
+        return;
+    }
+    assert!(pixels.iter().all(|p| (*p as usize) < format.colors()));
+    format.try_encode_into(&pixels, w * 8, h * mode as u32, mode, &mut encoded).unwrap();
+    assert_eq!(encoded, tiles);
+    if format == TileFormat::Gb {
+        assert_eq!(codec::gb_tiles_to_pixels(tiles, w, h, mode), pixels);
+        let mut out = vec![0xff; tiles.len()];
+        codec::try_encode_into(&pixels, w * 8, h * mode as u32, mode, &mut out).unwrap();
+        assert_eq!(out, tiles);
+    }
+}
@@ -0,0 +1,113 @@
+use core::fmt::Write;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Game Boy vertical refresh rate, one animation tick per frame
+pub const GB_FPS: f64 = 59.7275;
+
+/// A frame shows `tile_count` consecutive tiles starting at `first_tile`,
+/// laid out `columns` tiles wide (a simple metasprite), for `duration` GB frames
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AnimFrame
+{
+    pub first_tile: u32,
+    pub tile_count: u32,
+    pub columns: u32,
+    pub duration: u16
+}
+
+impl Default for AnimFrame {
+    fn default() -> Self {
+        Self {
+            first_tile: 0,
+            tile_count: 1,
+            columns: 1,
+            duration: 8
+        }
+    }
+}
+
+impl AnimFrame
+{
+    /// number of tile rows the frame occupies when laid out `columns` wide
+    pub fn rows(&self) -> u32
+    {
+        let columns = core::cmp::max(self.columns, 1);
+        self.tile_count / columns + (self.tile_count % columns != 0) as u32
+    }
+
+    pub fn contains(&self, tile: u32) -> bool
+    {
+        tile >= self.first_tile && tile - self.first_tile < self.tile_count
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Animation
+{
+    pub name: String,
+    pub frames: Vec<AnimFrame>
+}
+
+impl Animation
+{
+    pub fn new(name: impl Into<String>) -> Self
+    {
+        Self {
+            name: name.into(),
+            frames: vec![AnimFrame::default()]
+        }
+    }
+
+    /// length of one loop in GB frames
+    pub fn total_duration(&self) -> u32
+    {
+        self.frames.iter().map(|f| f.duration as u32).sum()
+    }
+
+    /// index of the frame visible at `tick` GB frames into the (looping) animation
+    pub fn frame_at(&self, tick: u64) -> Option<usize>
+    {
+        let total = self.total_duration() as u64;
+        if total == 0 {
+            return None;
+        }
+
+        let mut t = tick % total;
+        for (i, frame) in self.frames.iter().enumerate() {
+            if t < frame.duration as u64 {
+                return Some(i);
+            }
+            t -= frame.duration as u64;
+        }
+        None
+    }
+
+    /// index of the frame visible after `seconds` of playback
+    pub fn frame_at_time(&self, seconds: f64) -> Option<usize>
+    {
+        self.frame_at((seconds * GB_FPS) as u64)
+    }
+
+    /// RGBDS style frame table: frame count followed by
+    /// `first_tile, tile_count, columns, duration` per frame, all as words
+    /// since sheets have more than 256 tiles
+    pub fn frame_table(&self) -> String
+    {
+        let label: String = self.name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+
+        let mut table = String::new();
+        let _ = writeln!(table, "; {} frames, {} GB frames per loop", self.frames.len(), self.total_duration());
+        let _ = writeln!(table, "{}:", label);
+        let _ = writeln!(table, "    dw {}", self.frames.len());
+        for frame in &self.frames {
+            let _ = writeln!(table, "    dw {}, {}, {}, {}", frame.first_tile, frame.tile_count, frame.columns, frame.duration);
+        }
+        table
+    }
+}
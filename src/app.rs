@@ -1,23 +1,29 @@
 use egui_extras::{TableBuilder, Column};
 use egui::{RichText, Color32, Sense, Label, Vec2, Stroke};
-use std::fs::{File};
-use std::io::{BufWriter, Write, BufReader, Read};
 use std::{u8};
+use std::fmt::Write;
 use image;
 use image::{GenericImageView, DynamicImage};
 use image::imageops::FilterType;
-
-const TL_MAGIC: &'static [u8] = &[0x72,0x54, 0x69, 0x6c]; // rTil
-const TL_VERSION: &'static [u8] = &[1];
-
-#[derive(serde::Deserialize, serde::Serialize)]
-#[derive(Copy, Clone, PartialEq, Debug)]
-#[repr(u8)]
-pub enum TileMode
-{
-    Y8 = 8,
-    Y16 = 16
-}
+use crate::animation::{Animation, AnimFrame};
+use crate::aseprite;
+use crate::codec::{TileMode, gb_tiles_to_pixels, encode_row, decode_row};
+use crate::compress::Compression;
+use crate::files::{FileAction, Files, PickedFile};
+use crate::format::{TileFormat, pad_to_chr_banks};
+use crate::gbstudio::{self, AssetKind, Violation};
+use crate::project::{self, ExportTarget, MapDocument, Palette, Project, TileDocument};
+use crate::rip::TileRipper;
+use crate::rom;
+use crate::share;
+use crate::sheet::{TileSheet, quantize_luma_to};
+use crate::status::Status;
+use crate::storage::DocumentStorage;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::tiled;
+use crate::tilemap::Tilemap;
+use crate::tl;
+use crate::vram::{self, TileAddressing};
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum ResizeMode
@@ -29,19 +35,173 @@ pub enum ResizeMode
     ThumbnailExact
 }
 
+/// 2bpp formats use the first four entries
+const DEFAULT_PALETTE: [Color32; 16] = [
+    Color32::WHITE, Color32::LIGHT_GRAY, Color32::DARK_GRAY, Color32::BLACK,
+    Color32::from_rgb(255, 0, 77), Color32::from_rgb(255, 163, 0), Color32::from_rgb(255, 236, 39), Color32::from_rgb(0, 228, 54),
+    Color32::from_rgb(0, 135, 81), Color32::from_rgb(41, 173, 255), Color32::from_rgb(29, 43, 83), Color32::from_rgb(131, 118, 156),
+    Color32::from_rgb(255, 119, 168), Color32::from_rgb(255, 204, 170), Color32::from_rgb(171, 82, 54), Color32::from_rgb(126, 37, 83)
+];
+
+/// hover-paint shortcuts, the n-th key paints palette index n
+const PAINT_KEYS: [egui::Key; 16] = [
+    egui::Key::Num1, egui::Key::Num2, egui::Key::Num3, egui::Key::Num4, egui::Key::Num5,
+    egui::Key::Num6, egui::Key::Num7, egui::Key::Num8, egui::Key::Num9, egui::Key::Num0,
+    egui::Key::Q, egui::Key::W, egui::Key::E, egui::Key::R, egui::Key::T, egui::Key::Y
+];
+
+/// overwrites the leading colors of `palette` with `colors`
+fn set_palette(palette: &mut [Color32], colors: &[[u8; 4]])
+{
+    for (color, rgba) in palette.iter_mut().zip(colors) {
+        *color = Color32::from_rgba_unmultiplied(rgba[0], rgba[1], rgba[2], rgba[3]);
+    }
+}
+
+/// storage key of the crash-recovery slot, cleared whenever the app state is saved
+const RECOVERY_KEY: &str = "rzrtiles_recovery";
+/// seconds between writes of unsaved changes to the recovery slot
+const AUTOSAVE_INTERVAL: f64 = 5.0;
+
+/// The canvas as it was when the app state was last autosaved
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Recovery
+{
+    path: String,
+    format: TileFormat,
+    sheet: TileSheet
+}
+
+/// whether the service worker of the web build installed a newer version, set by index.html
+#[cfg(target_arch = "wasm32")]
+fn update_available() -> bool
+{
+    web_sys::window().map_or(false, |w| js_sys::Reflect::has(&w, &"rzrtiles_update".into()).unwrap_or(false))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn update_available() -> bool
+{
+    false
+}
+
+/// lets the waiting service worker take over, index.html reloads the page once it did
+#[cfg(target_arch = "wasm32")]
+fn apply_update() -> Result<(), String>
+{
+    use wasm_bindgen::JsCast;
+
+    let apply = web_sys::window()
+        .and_then(|w| js_sys::Reflect::get(&w, &"rzrtiles_apply_update".into()).ok())
+        .and_then(|f| f.dyn_into::<js_sys::Function>().ok())
+        .ok_or("the page can't apply updates")?;
+    apply.call0(&wasm_bindgen::JsValue::NULL).map(|_| ()).map_err(|err| format!("{:?}", err))
+}
+
+/// unpinned entries kept in the recent files list
+const MAX_RECENT_FILES: usize = 10;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Clone, PartialEq, Debug)]
+struct RecentFile
+{
+    path: String,
+    /// pinned entries are never dropped from the list
+    pinned: bool
+}
+
+const SHORTCUT_NEW: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::N);
+const SHORTCUT_OPEN: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::O);
+const SHORTCUT_SAVE: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::S);
+const SHORTCUT_SAVE_AS: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers { shift: true, ..egui::Modifiers::COMMAND }, egui::Key::S);
+
+/// whether `sheet` differs from the file it was loaded from or saved to, a blank untitled sheet has nothing to lose
+fn unsaved(sheet: &TileSheet, clean: &Option<TileSheet>) -> bool
+{
+    match clean {
+        Some(clean) => clean != sheet,
+        None => sheet.pixels.iter().any(|p| *p != 0)
+    }
+}
+
+/// file name without extension, used as tab title
+fn document_name(path: impl AsRef<std::path::Path>) -> String
+{
+    path.as_ref().file_stem().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+/// Headerless tile data waiting for the user to pick its format and layout
+struct RawImport
+{
+    name: String,
+    data: Vec<u8>,
+    format: TileFormat,
+    mode: TileMode,
+    /// tiles per row
+    columns: u32
+}
+
+/// State of the document browser of the web build
+struct Browser
+{
+    /// name the edited sheet is saved under
+    name: String,
+    /// file waiting for the delete to be confirmed
+    delete: Option<String>
+}
+
+/// documents are saved to the file system natively and to IndexedDB on the web,
+/// where `TemplateApp::new` replaces this with the database once it can be opened
+#[cfg(not(target_arch = "wasm32"))]
+fn default_storage() -> Box<dyn DocumentStorage>
+{
+    Box::new(crate::storage::FileStorage::default())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn default_storage() -> Box<dyn DocumentStorage>
+{
+    Box::new(crate::storage::MemoryStorage::default())
+}
+
+/// An open tile document, the active one is edited in place in `TemplateApp::sheet`
+struct Tab
+{
+    name: String,
+    path: String,
+    sheet: TileSheet,
+    format: TileFormat,
+    /// sheet as last loaded or saved
+    clean: Option<TileSheet>
+}
+
+/// Exported size per compression of `sheet` in `format`
+struct CompressedSizes
+{
+    sheet: TileSheet,
+    format: TileFormat,
+    sizes: Vec<(Compression, usize)>
+}
+
+/// What was held back by the unsaved changes prompt
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum ClosePrompt
+{
+    /// closing the app
+    Quit,
+    /// closing the tab at this index
+    Tab(usize),
+    /// reloading the web build into its new version
+    Reload
+}
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct TemplateApp {
-    #[serde(skip)]
-    pixels: Vec<u8>,
-    #[serde(skip)]
-    width: u32,
-    #[serde(skip)]
-    height: u32,
-    #[serde(skip)]
-    mode: TileMode,
-    palette: [Color32; 4],
+    sheet: TileSheet,
+    format: TileFormat,
+    palette: Vec<Color32>,
     picked_path: String,
     scale: f32,
     instant_save: bool,
@@ -50,227 +210,2062 @@ pub struct TemplateApp {
     #[serde(skip)]
     filter_type: FilterType,
     #[serde(skip)]
-    resize_mode: ResizeMode
+    resize_mode: ResizeMode,
+    animations: Vec<Animation>,
+    selected_animation: usize,
+    selected_frame: usize,
+    onion_skin: bool,
+    show_animation: bool,
+    #[serde(skip)]
+    playing: bool,
+    show_ripper: bool,
+    #[serde(skip)]
+    ripper: Option<TileRipper>,
+    /// tile range of the ripper extracted into the editor
+    #[serde(skip)]
+    rip_first: u32,
+    #[serde(skip)]
+    rip_count: u32,
+    /// tile addressing for raw VRAM dumps, save states carry their own LCDC
+    #[serde(skip)]
+    vram_addressing: TileAddressing,
+    #[serde(skip)]
+    tilemaps: Vec<(String, Tilemap)>,
+    #[serde(skip)]
+    selected_tilemap: usize,
+    show_tilemap: bool,
+    #[serde(skip)]
+    tilemap_texture: Option<egui::TextureHandle>,
+    /// sheet, map and palette the texture was rendered from
+    #[serde(skip)]
+    tilemap_source: Option<(TileSheet, usize, Vec<Color32>)>,
+    /// applied by Export raw
+    compression: Compression,
+    #[serde(skip)]
+    compressed_sizes: Option<CompressedSizes>,
+    show_gbstudio: bool,
+    #[serde(skip)]
+    gbstudio_kind: AssetKind,
+    /// rules the sheet broke when it was last validated
+    #[serde(skip)]
+    gbstudio_violations: Option<Vec<Violation>>,
+    /// most recently used first
+    recent_files: Vec<RecentFile>,
+    /// open the most recent file on startup
+    reopen_last: bool,
+    /// project file, empty while no project is open
+    project_path: String,
+    #[serde(skip)]
+    project: Option<Project>,
+    #[serde(skip)]
+    tabs: Vec<Tab>,
+    #[serde(skip)]
+    active_tab: usize,
+    /// sheet as last written to storage, by `save` or the recovery autosave
+    #[serde(skip)]
+    autosaved: Option<TileSheet>,
+    #[serde(skip)]
+    last_autosave: f64,
+    /// `sheet` as last loaded or saved, None for new sheets
+    #[serde(skip)]
+    clean: Option<TileSheet>,
+    /// `clean` is read once the storage is ready
+    #[serde(skip)]
+    clean_pending: bool,
+    /// width and height in tiles and mode of the sheet File > New creates, while its dialog is open
+    #[serde(skip)]
+    new_sheet: Option<(u32, u32, TileMode)>,
+    /// set when closing was cancelled because of unsaved changes
+    #[serde(skip)]
+    close_prompt: Option<ClosePrompt>,
+    #[serde(skip)]
+    allowed_to_close: bool,
+    /// errors and results shown in the status line
+    #[serde(skip)]
+    status: Status,
+    #[serde(skip)]
+    files: Files,
+    #[serde(skip)]
+    storage: Box<dyn DocumentStorage>,
+    /// open while the document browser is shown
+    #[serde(skip)]
+    browser: Option<Browser>,
+    #[serde(skip)]
+    raw_import: Option<RawImport>,
+    /// unsaved work found in the recovery slot on startup
+    #[serde(skip)]
+    recovery: Option<Recovery>,
+    /// "Later" was clicked on the update notice
+    #[serde(skip)]
+    update_dismissed: bool,
+    #[serde(skip)]
+    hex_str: String,
+    /// sheet the hex view was printed from
+    #[serde(skip)]
+    hex_sheet: Option<TileSheet>
 }
 
 impl Default for TemplateApp {
     fn default() -> Self {
+        let status = Status::default();
         Self {
-            pixels: vec![0; 8*8],
-            width: 8,
-            height: 8,
-            mode: TileMode::Y8,
-            palette: [Color32::WHITE, Color32::LIGHT_GRAY, Color32::DARK_GRAY, Color32::BLACK],
+            sheet: TileSheet::default(),
+            format: TileFormat::Gb,
+            palette: DEFAULT_PALETTE.to_vec(),
             picked_path: String::from("tiles.tl"),
             scale: 1.0,
             instant_save: false,
             image: DynamicImage::default(),
             filter_type: FilterType::Lanczos3,
-            resize_mode: ResizeMode::Default
+            resize_mode: ResizeMode::Default,
+            animations: Vec::new(),
+            selected_animation: 0,
+            selected_frame: 0,
+            onion_skin: false,
+            show_animation: false,
+            playing: true,
+            show_ripper: false,
+            ripper: None,
+            rip_first: 0,
+            rip_count: 1,
+            vram_addressing: TileAddressing::Unsigned,
+            tilemaps: Vec::new(),
+            selected_tilemap: 0,
+            show_tilemap: false,
+            tilemap_texture: None,
+            tilemap_source: None,
+            compression: Compression::None,
+            compressed_sizes: None,
+            show_gbstudio: false,
+            gbstudio_kind: AssetKind::Background,
+            gbstudio_violations: None,
+            recent_files: Vec::new(),
+            reopen_last: false,
+            project_path: String::new(),
+            project: None,
+            tabs: Vec::new(),
+            active_tab: 0,
+            autosaved: None,
+            last_autosave: 0.0,
+            clean: None,
+            clean_pending: false,
+            new_sheet: None,
+            close_prompt: None,
+            allowed_to_close: false,
+            files: Files::new(status.clone()),
+            status,
+            storage: default_storage(),
+            browser: None,
+            raw_import: None,
+            recovery: None,
+            update_dismissed: false,
+            hex_str: String::new(),
+            hex_sheet: None
+        }
+    }
+}
+
+impl TemplateApp {
+    /// Called once before the first frame.
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        // This is also where you can customize the look and feel of egui using
+        // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
+
+        // Load previous app state (if any).
+        // Note that you must enable the `persistence` feature for this to work.
+        #[allow(unused_mut)] // only the web build opens share links
+        let mut app = match cc.storage {
+            Some(storage) => TemplateApp::restore(storage),
+            None => TemplateApp::default()
+        };
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            app.storage = Box::new(crate::storage::IndexedDbStorage::open(&cc.egui_ctx, app.status.clone()));
+            app.clean_pending = true;
+            app.open_share_link();
+        }
+        app
+    }
+
+    /// app state saved by `save`, with unsaved work from the recovery slot to offer
+    fn restore(storage: &dyn eframe::Storage) -> Self
+    {
+        let mut app: TemplateApp = eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        // state saved before 4bpp support only has 4 colors
+        let len = app.palette.len();
+        if len < DEFAULT_PALETTE.len() {
+            app.palette.extend_from_slice(&DEFAULT_PALETTE[len..]);
+        }
+
+        // the recovery slot only survives when the app didn't get to save its state
+        let recovery: Option<Recovery> = eframe::get_value(storage, RECOVERY_KEY).flatten();
+        app.recovery = recovery.filter(|r| r.sheet != app.sheet);
+        app.autosaved = Some(app.sheet.clone());
+        app.restore_clean();
+
+        if app.reopen_last && app.recovery.is_none() {
+            app.reopen_last_file();
+        }
+        app
+    }
+
+    /// reads the files the restored documents were last saved to, the web build once its storage is loaded
+    fn restore_clean(&mut self)
+    {
+        let storage = &*self.storage;
+        let read = |path: &str| storage.read(path).ok()
+            .and_then(|data| tl::read_tl(&data).ok())
+            .map(|(sheet, _)| sheet);
+        // the canvas is kept in a tab when a share link is opened
+        for tab in &mut self.tabs {
+            if tab.clean.is_none() && !tab.path.is_empty() {
+                tab.clean = read(&tab.path);
+            }
+        }
+        if self.clean.is_none() && !self.picked_path.is_empty() {
+            self.clean = read(&self.picked_path);
+        }
+    }
+
+    /// number of palette entries the current format can use
+    pub fn colors(&self) -> u8
+    {
+        self.format.colors() as u8
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> u8
+    {
+        self.sheet.get(x, y)
+    }
+    pub fn set(&mut self, x: u32, y: u32, val: u8)
+    {
+        self.sheet.set(x, y, val)
+    }
+
+    pub fn export(&self) -> Vec<u8>
+    {
+        self.sheet.export()
+    }
+
+    /// w and h number of tiles
+    pub fn import(&mut self, data: &[u8], w: u32, h: u32)
+    {
+        self.sheet = TileSheet {
+            pixels: gb_tiles_to_pixels(data, w, h, self.sheet.mode),
+            width: w*8,
+            height: h*self.sheet.mode as u32,
+            mode: self.sheet.mode
+        };
+    }
+
+    /// the sheet as .tl file
+    fn tl_data(&self) -> Option<Vec<u8>>
+    {
+        match tl::to_tl_as(&self.sheet, self.format) {
+            Ok(data) => Some(data),
+            Err(err) => {
+                self.status.error(format!("save: {}", err));
+                None
+            }
+        }
+    }
+
+    /// returns whether the file was written
+    pub fn save_to_disk(&mut self, path: impl Into<String>) -> bool
+    {
+        let data = match self.tl_data() {
+            Some(data) => data,
+            None => return false
+        };
+
+        if let Err(err) = self.storage.write(&path.into(), &data) {
+            self.status.error(format!("save: {}", err));
+            return false;
+        }
+        true
+    }
+
+    pub fn load_from_disk(&mut self, path: impl Into<String>)
+    {
+        let data = match self.storage.read(&path.into())
+        {
+            Ok(data) => data,
+            Err(err) =>
+            {
+                self.status.error(format!("load: {}", err));
+                return;
+            }
+        };
+
+        match tl::read_tl(&data) {
+            Ok((sheet, format)) => {
+                self.clean = Some(sheet.clone());
+                self.sheet = sheet;
+                self.format = format;
+            }
+            Err(err) => self.status.error(format!("load: {}", err))
+        }
+    }
+
+    /// switches to the tab of `path`, loading it into a new tab if it isn't open yet
+    fn open_tab(&mut self, name: impl Into<String>, path: impl Into<String>)
+    {
+        let path = path.into();
+        self.keep_canvas();
+        if let Some(i) = self.tabs.iter().position(|t| t.path == path) {
+            self.switch_tab(i);
+            return;
+        }
+
+        match self.storage.read(&path) {
+            Ok(data) => if self.open_document(name, path.clone(), &data) && !cfg!(target_arch = "wasm32") {
+                self.add_recent(&path);
+            },
+            Err(err) => self.status.error(format!("open tab: {}", err))
+        }
+    }
+
+    /// .tl `data` in a new tab, `path` is where Save writes it
+    fn open_document(&mut self, name: impl Into<String>, path: String, data: &[u8]) -> bool
+    {
+        match tl::read_tl(data) {
+            Ok((sheet, format)) => {
+                self.keep_canvas();
+                self.tabs.push(Tab { name: name.into(), path, clean: Some(sheet.clone()), sheet, format });
+                self.switch_tab(self.tabs.len() - 1);
+                true
+            }
+            Err(err) => {
+                self.status.error(format!("open tab: {}", err));
+                false
+            }
+        }
+    }
+
+    /// a file from `Files`, web builds only know its name
+    fn open_picked(&mut self, action: FileAction, file: PickedFile)
+    {
+        let path = file.path.as_ref().map(|p| p.display().to_string());
+        match action {
+            FileAction::Open => match path {
+                Some(path) => self.open_tab(document_name(&file.name), path),
+                None => {
+                    self.open_document(document_name(&file.name), file.name.clone(), &file.data);
+                }
+            },
+            FileAction::Image => self.import_image(&file.data),
+            FileAction::Raw => {
+                // the extension tells 2bpp formats from 4bpp ones, not which of them
+                let extension = file.name.rsplit('.').next().unwrap_or_default().to_ascii_lowercase();
+                let format = match extension.as_str() {
+                    "chr" => TileFormat::Nes,
+                    "2bpp" if self.format.bits_per_pixel() != 2 => TileFormat::Gb,
+                    "4bpp" if self.format.bits_per_pixel() != 4 => TileFormat::Snes,
+                    _ => self.format
+                };
+                self.raw_import = Some(RawImport { name: file.name, data: file.data, format, mode: self.sheet.mode, columns: 16 });
+            }
+            FileAction::Vram => self.import_vram(&file.data),
+            FileAction::Aseprite => self.import_aseprite(&file.name, &file.data),
+            FileAction::Rom => {
+                self.ripper = Some(TileRipper { format: self.format, mode: self.sheet.mode, ..TileRipper::new(file.data) });
+                self.rip_first = 0;
+                self.rip_count = 1;
+            }
+            FileAction::Project => {
+                match std::str::from_utf8(&file.data).map_err(|err| err.to_string()).and_then(|json| Project::from_json(json).map_err(|err| err.to_string())) {
+                    Ok(loaded) => {
+                        self.project = Some(loaded);
+                        self.project_path = path.unwrap_or(file.name);
+                    }
+                    Err(err) => self.status.error(format!("open project: {}", err))
+                }
+            }
+            FileAction::Archive => self.import_archive(file),
+        }
+    }
+
+    /// files dropped onto the window, by their extension
+    fn drop_files(&mut self, ctx: &egui::Context)
+    {
+        for dropped in ctx.input(|i| i.raw.dropped_files.clone()) {
+            let data = match (&dropped.bytes, &dropped.path) {
+                (Some(bytes), _) => bytes.to_vec(),
+                (None, Some(path)) => match std::fs::read(path) {
+                    Ok(data) => data,
+                    Err(err) => {
+                        self.status.error(format!("drop {}: {}", path.display(), err));
+                        continue;
+                    }
+                },
+                (None, None) => continue
+            };
+
+            let name = match &dropped.path {
+                Some(path) => path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+                None => dropped.name.clone()
+            };
+            match FileAction::for_file(&name) {
+                Some(action) => self.files.push(action, PickedFile { name, path: dropped.path, data }),
+                None => self.status.error(format!("drop {}: unsupported file type", name))
+            }
+        }
+    }
+
+    /// darkens the window while files are dragged over it and lists what dropping them does
+    fn drop_zone_ui(&self, ctx: &egui::Context)
+    {
+        let hovered = ctx.input(|i| i.raw.hovered_files.clone());
+        if hovered.is_empty() {
+            return;
+        }
+
+        let mut text = String::from("Drop to");
+        for file in &hovered {
+            // the web build doesn't know the names before the drop
+            let name = match &file.path {
+                Some(path) => path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+                None => continue
+            };
+            let action = FileAction::for_file(&name).map_or("skip, unsupported file type", FileAction::name);
+            let _ = write!(text, "\n{}: {}", action, name);
+        }
+        if text.len() == "Drop to".len() {
+            text.push_str(" open or import");
+        }
+
+        let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("drop_zone")));
+        let rect = ctx.screen_rect();
+        painter.rect(rect.shrink(4.0), 8.0, Color32::from_black_alpha(160), Stroke::new(4.0, ctx.style().visuals.selection.bg_fill));
+        painter.text(rect.center(), egui::Align2::CENTER_CENTER, text, egui::TextStyle::Heading.resolve(&ctx.style()), Color32::WHITE);
+    }
+
+    /// asks for the format and layout of dropped or picked raw tiles
+    fn raw_import_ui(&mut self, ctx: &egui::Context)
+    {
+        let import = match &mut self.raw_import {
+            Some(import) => import,
+            None => return
+        };
+
+        let mut done = None;
+        egui::Window::new("Import raw tiles").collapsible(false).resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label(format!("{} ({} bytes)", import.name, import.data.len()));
+            egui::Grid::new("raw_import").show(ui, |ui| {
+                ui.label("Format");
+                egui::ComboBox::from_id_source("raw_import_format")
+                    .selected_text(import.format.name())
+                    .show_ui(ui, |ui| {
+                        for format in TileFormat::ALL {
+                            ui.selectable_value(&mut import.format, format, format.name());
+                        }
+                    });
+                ui.end_row();
+                ui.label("Mode");
+                egui::ComboBox::from_id_source("raw_import_mode")
+                    .selected_text(format!("{:?}", import.mode))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut import.mode, TileMode::Y8, "8x8");
+                        ui.selectable_value(&mut import.mode, TileMode::Y16, "8x16");
+                    });
+                ui.end_row();
+                ui.label("Tiles per row");
+                ui.add(egui::DragValue::new(&mut import.columns).clamp_range(1..=64));
+                ui.end_row();
+            });
+
+            let bytes_per_tile = import.format.bytes_per_tile(import.mode);
+            let left = import.data.len() % bytes_per_tile;
+            ui.label(format!("{} tiles{}", import.data.len() / bytes_per_tile,
+                if left > 0 { format!(", {} bytes left over", left) } else { String::new() }));
+            ui.horizontal(|ui| {
+                if ui.button("Import").clicked() {
+                    done = Some(true);
+                }
+                if ui.button("Cancel").clicked() {
+                    done = Some(false);
+                }
+            });
+        });
+
+        match done {
+            Some(true) => if let Some(import) = self.raw_import.take() {
+                self.format = import.format;
+                self.sheet = TileSheet::from_tiles_as(import.format, &import.data, import.columns, import.mode);
+            },
+            Some(false) => self.raw_import = None,
+            None => {}
+        }
+    }
+
+    /// moves `path` to the front of the recent files
+    fn add_recent(&mut self, path: &str)
+    {
+        let pinned = match self.recent_files.iter().position(|r| r.path == path) {
+            Some(i) => self.recent_files.remove(i).pinned,
+            None => false
+        };
+        self.recent_files.insert(0, RecentFile { path: path.to_owned(), pinned });
+
+        let mut unpinned = 0;
+        self.recent_files.retain(|r| {
+            unpinned += !r.pinned as usize;
+            r.pinned || unpinned <= MAX_RECENT_FILES
+        });
+    }
+
+    /// opens the most recent file that still exists, keeping the restored canvas if it was that file
+    fn reopen_last_file(&mut self)
+    {
+        let last = match self.recent_files.iter().find(|r| std::path::Path::new(&r.path).exists()) {
+            Some(recent) => recent.path.clone(),
+            None => return
+        };
+
+        if self.picked_path == last {
+            // the saved canvas is that file, maybe with edits that never made it to disk
+            self.tabs.push(Tab { name: document_name(&last), path: last, sheet: self.sheet.clone(), format: self.format, clean: self.clean.clone() });
+            self.active_tab = 0;
+        } else {
+            self.open_tab(document_name(&last), last);
+        }
+    }
+
+    fn recent_files_ui(&mut self, ui: &mut egui::Ui)
+    {
+        let mut open = None;
+        let mut remove = None;
+        for (i, recent) in self.recent_files.iter_mut().enumerate() {
+            let exists = std::path::Path::new(&recent.path).exists();
+            ui.horizontal(|ui| {
+                ui.toggle_value(&mut recent.pinned, "📌").on_hover_text("keep in the list");
+                let button = ui.add_enabled(exists, egui::Button::new(document_name(&recent.path)))
+                    .on_hover_text(&recent.path)
+                    .on_disabled_hover_text(format!("{} is missing", recent.path));
+                if button.clicked() {
+                    open = Some(recent.path.clone());
+                    ui.close_menu();
+                }
+                if !exists && ui.small_button("x").on_hover_text("remove from the list").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+
+        if self.recent_files.is_empty() {
+            ui.label("No recent files");
+        }
+        ui.separator();
+        if ui.button("Remove missing").clicked() {
+            self.recent_files.retain(|r| std::path::Path::new(&r.path).exists());
+        }
+        if ui.button("Clear unpinned").clicked() {
+            self.recent_files.retain(|r| r.pinned);
+        }
+
+        if let Some(i) = remove {
+            self.recent_files.remove(i);
+        }
+        if let Some(path) = open {
+            self.open_tab(document_name(&path), path);
+        }
+    }
+
+    /// turns unsaved work on a canvas without tabs into a tab, so opening another document doesn't replace it
+    fn keep_canvas(&mut self)
+    {
+        if self.tabs.is_empty() && self.is_dirty() {
+            let name = if self.picked_path.is_empty() { String::from("untitled") } else { document_name(&self.picked_path) };
+            self.tabs.push(Tab { name, path: self.picked_path.clone(), sheet: self.sheet.clone(), format: self.format, clean: self.clean.clone() });
+            self.active_tab = 0;
+        }
+    }
+
+    /// keeps the edits of the active tab and makes tab `i` the edited sheet
+    fn switch_tab(&mut self, i: usize)
+    {
+        if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+            tab.sheet = self.sheet.clone();
+            tab.format = self.format;
+            tab.clean = self.clean.clone();
+        }
+        if let Some(tab) = self.tabs.get(i) {
+            self.sheet = tab.sheet.clone();
+            self.format = tab.format;
+            self.clean = tab.clean.clone();
+            self.picked_path = tab.path.clone();
+            self.active_tab = i;
+        }
+    }
+
+    /// link to the web build carrying the edited sheet, format and palette
+    fn share_link(&self) -> Option<String>
+    {
+        #[cfg(target_arch = "wasm32")]
+        let base = web_sys::window().and_then(|w| w.location().href().ok()).unwrap_or_else(|| share::WEB_URL.to_owned());
+        #[cfg(not(target_arch = "wasm32"))]
+        let base = share::WEB_URL.to_owned();
+
+        let palette: Vec<[u8; 3]> = self.palette.iter().map(|c| [c.r(), c.g(), c.b()]).collect();
+        match share::link(&base, &self.sheet, self.format, &palette) {
+            Ok(link) => Some(link),
+            Err(err) => {
+                self.status.error(format!("share link: {}", err));
+                None
+            }
+        }
+    }
+
+    /// opens the sheet in the page's URL fragment in a new tab
+    #[cfg(target_arch = "wasm32")]
+    fn open_share_link(&mut self)
+    {
+        let href = web_sys::window().and_then(|w| w.location().href().ok()).unwrap_or_default();
+        match share::from_link(&href) {
+            Some(Ok(shared)) => {
+                self.keep_canvas();
+                let colors: Vec<[u8; 4]> = shared.palette.iter().map(|c| [c[0], c[1], c[2], 255]).collect();
+                set_palette(&mut self.palette, &colors);
+                self.tabs.push(Tab { name: String::from("shared"), path: String::new(), sheet: shared.sheet, format: shared.format, clean: None });
+                self.switch_tab(self.tabs.len() - 1);
+            }
+            Some(Err(err)) => self.status.error(format!("share link: {}", err)),
+            None => ()
+        }
+    }
+
+    /// whether the edited sheet has changes that aren't saved
+    fn is_dirty(&self) -> bool
+    {
+        unsaved(&self.sheet, &self.clean)
+    }
+
+    fn tab_dirty(&self, i: usize) -> bool
+    {
+        match self.tabs.get(i) {
+            Some(_) if i == self.active_tab => self.is_dirty(),
+            Some(tab) => unsaved(&tab.sheet, &tab.clean),
+            None => false
+        }
+    }
+
+    /// names of the documents with unsaved changes
+    fn unsaved_documents(&self) -> Vec<String>
+    {
+        if self.tabs.is_empty() {
+            return if self.is_dirty() { vec![self.picked_path.clone()] } else { Vec::new() };
+        }
+        (0..self.tabs.len()).filter(|i| self.tab_dirty(*i)).map(|i| self.tabs[i].name.clone()).collect()
+    }
+
+    /// empty sheet in a new untitled tab
+    fn new_document(&mut self, width: u32, height: u32, mode: TileMode)
+    {
+        self.keep_canvas();
+        let sheet = TileSheet::new(width * 8, height * mode as u32, mode);
+        self.tabs.push(Tab { name: String::from("untitled"), path: String::new(), sheet, format: self.format, clean: None });
+        self.switch_tab(self.tabs.len() - 1);
+    }
+
+    /// the web build opens documents from the browser's storage
+    fn open_dialog(&mut self, ctx: &egui::Context)
+    {
+        if cfg!(target_arch = "wasm32") {
+            self.open_browser();
+        } else {
+            self.files.pick(ctx, FileAction::Open, "rzrTiles", &["tl"]);
+        }
+    }
+
+    fn open_browser(&mut self)
+    {
+        let name = if self.picked_path.is_empty() { String::from("tiles.tl") } else { self.picked_path.clone() };
+        self.browser = Some(Browser { name, delete: None });
+    }
+
+    /// saves to the document's file, asks for one if it has none yet
+    fn save_document(&mut self)
+    {
+        if self.picked_path.is_empty() {
+            self.save_document_as();
+        } else {
+            self.save_document_to(self.picked_path.clone());
+        }
+    }
+
+    /// asks for a file natively, the web build names the document in the document browser
+    fn save_document_as(&mut self)
+    {
+        if cfg!(target_arch = "wasm32") {
+            self.open_browser();
+            return;
+        }
+        let name = if self.picked_path.is_empty() { String::from("tiles.tl") } else { format!("{}.tl", document_name(&self.picked_path)) };
+        if let Some(path) = self.files.save_path(&name, "rzrTiles", &["tl"]) {
+            self.save_document_to(path);
+        }
+    }
+
+    fn save_document_to(&mut self, path: String)
+    {
+        if self.save_to_disk(path.clone()) {
+            self.saved_as(path);
+        }
+    }
+
+    /// the sheet was written to `path`
+    fn saved_as(&mut self, path: String)
+    {
+        self.clean = Some(self.sheet.clone());
+        if !cfg!(target_arch = "wasm32") {
+            self.add_recent(&path);
+        }
+        if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+            tab.name = document_name(&path);
+            tab.path = path.clone();
+        }
+        self.picked_path = path;
+    }
+
+    /// drops the unsaved changes of the edited sheet
+    fn revert(&mut self)
+    {
+        match &self.clean {
+            Some(clean) => self.sheet = clean.clone(),
+            None => self.sheet.pixels.fill(0)
+        }
+    }
+
+    /// asks first if the tab has unsaved changes
+    fn close_tab(&mut self, i: usize)
+    {
+        if self.tab_dirty(i) {
+            self.close_prompt = Some(ClosePrompt::Tab(i));
+        } else {
+            self.remove_tab(i);
+        }
+    }
+
+    fn remove_tab(&mut self, i: usize)
+    {
+        if i >= self.tabs.len() {
+            return;
+        }
+        if i == self.active_tab {
+            self.tabs.remove(i);
+            // the sheet still holds the closed document, show its neighbour instead
+            self.active_tab = usize::MAX;
+            if !self.tabs.is_empty() {
+                self.switch_tab(std::cmp::min(i, self.tabs.len() - 1));
+            } else {
+                self.active_tab = 0;
+            }
+        } else {
+            self.tabs.remove(i);
+            if i < self.active_tab {
+                self.active_tab -= 1;
+            }
+        }
+    }
+
+    fn tabs_ui(&mut self, ui: &mut egui::Ui)
+    {
+        let mut switch = None;
+        let mut close = None;
+        ui.horizontal_wrapped(|ui| {
+            for (i, tab) in self.tabs.iter().enumerate() {
+                let name = if self.tab_dirty(i) { format!("{}*", tab.name) } else { tab.name.clone() };
+                if ui.selectable_label(i == self.active_tab, name).on_hover_text(&tab.path).clicked() {
+                    switch = Some(i);
+                }
+                if ui.small_button("x").clicked() {
+                    close = Some(i);
+                }
+                ui.separator();
+            }
+        });
+
+        if let Some(i) = switch {
+            self.switch_tab(i);
+        }
+        if let Some(i) = close {
+            self.close_tab(i);
+        }
+    }
+
+    fn save_project(&mut self)
+    {
+        if self.project.is_none() {
+            return;
+        }
+        if self.project_path.is_empty() {
+            // the web build keeps projects in the document browser
+            match self.files.save_path("project.json", "rzrTiles project", &["json"]) {
+                Some(path) => self.project_path = path,
+                None if cfg!(target_arch = "wasm32") => self.project_path = String::from("project.json"),
+                None => return
+            }
+        }
+
+        let project_path = std::path::PathBuf::from(&self.project_path);
+        let project = match self.project.as_mut() {
+            Some(project) => project,
+            None => return
+        };
+        // the animations belong to the edited document
+        let edited = project.documents.iter_mut().find(|d| project::resolve(&project_path, &d.path).display().to_string() == self.picked_path);
+        if let Some(document) = edited {
+            document.animations = self.animations.clone();
+        }
+        let json = project.to_json();
+        if let Err(err) = self.storage.write(&self.project_path, json.as_bytes()) {
+            self.status.error(format!("save project: {}", err));
+        }
+    }
+
+    fn export_archive(&self)
+    {
+        let project = match &self.project {
+            Some(project) => project,
+            None => return
+        };
+        match project.export_archive(&*self.storage, &self.project_path) {
+            Ok(data) => {
+                let name = if self.project_path.is_empty() { String::from("project") } else { document_name(&self.project_path) };
+                self.files.save(&format!("{}.tlproj", name), "rzrTiles project archive", &["tlproj"], &data);
+            }
+            Err(err) => self.status.error(format!("export archive: {}", err))
+        }
+    }
+
+    /// unpacks the archive `file` into a directory named after it and opens its project
+    fn import_archive(&mut self, file: PickedFile)
+    {
+        let dir = match &file.path {
+            Some(path) => path.with_extension("").display().to_string(),
+            None => document_name(&file.name)
+        };
+        match project::import_archive(&mut *self.storage, &dir, &file.data) {
+            Ok((path, loaded)) => {
+                self.project = Some(loaded);
+                self.project_path = path;
+            }
+            Err(err) => self.status.error(format!("import archive: {}", err))
+        }
+    }
+
+    fn project_ui(&mut self, ui: &mut egui::Ui)
+    {
+        ui.horizontal(|ui| {
+            if ui.button("New").clicked() {
+                self.project = Some(Project::default());
+                self.project_path.clear();
+            }
+
+            if ui.button("Open").clicked() {
+                self.files.pick(ui.ctx(), FileAction::Project, "rzrTiles project", &["json"]);
+            }
+
+            if ui.add_enabled(self.project.is_some(), egui::Button::new("Save")).clicked() {
+                self.save_project();
+            }
+
+            if ui.add_enabled(self.project.is_some(), egui::Button::new("Build")).on_hover_text("write every export target").clicked() {
+                if let Some(project) = &self.project {
+                    match project.build(&mut *self.storage, &self.project_path) {
+                        Ok(written) if written.is_empty() => self.status.info("nothing to build, the project has no export targets"),
+                        Ok(written) => {
+                            let files: Vec<String> = written.iter().map(|(path, bytes)| format!("{} ({} bytes)", path, bytes)).collect();
+                            self.status.info(format!("built {}", files.join(", ")));
+                        }
+                        Err(err) => self.status.error(format!("build: {}", err))
+                    }
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui.add_enabled(self.project.is_some(), egui::Button::new("Export archive")).on_hover_text("the project and its documents in one .tlproj file").clicked() {
+                self.export_archive();
+            }
+            if ui.button("Import archive").on_hover_text("unpacks a .tlproj file into a directory named after it").clicked() {
+                self.files.pick(ui.ctx(), FileAction::Archive, "rzrTiles project archive", &["tlproj"]);
+            }
+        });
+
+        let project_path = std::path::PathBuf::from(&self.project_path);
+        let project = match self.project.as_mut() {
+            Some(project) => project,
+            None => {
+                ui.label("No project, press New or Open.");
+                return;
+            }
+        };
+        ui.label(if self.project_path.is_empty() { "unsaved project" } else { self.project_path.as_str() });
+
+        let mut open = None;
+        egui::CollapsingHeader::new(format!("Documents ({})", project.documents.len())).default_open(true).show(ui, |ui| {
+            for doc in &project.documents {
+                if ui.selectable_label(self.picked_path == project::resolve(&project_path, &doc.path).display().to_string(), &doc.name).on_hover_text(&doc.path).clicked() {
+                    open = Some(doc.clone());
+                }
+            }
+
+            if ui.button("Add open document").on_hover_text("the .tl file being edited").clicked() && !self.picked_path.is_empty() {
+                let path = project::relative(&project_path, std::path::Path::new(&self.picked_path));
+                if !project.documents.iter().any(|d| d.path == path) {
+                    let name = document_name(&self.picked_path);
+                    project.documents.push(TileDocument { name, path, palette: None, animations: self.animations.clone() });
+                }
+            }
+        });
+
+        egui::CollapsingHeader::new(format!("Palettes ({})", project.palettes.len())).show(ui, |ui| {
+            for palette in &project.palettes {
+                if ui.button(&palette.name).on_hover_text("use this palette").clicked() {
+                    set_palette(&mut self.palette, &palette.colors);
+                }
+            }
+
+            if ui.button("Add current palette").clicked() {
+                let colors = self.palette.iter().map(|c| c.to_array()).collect();
+                project.palettes.push(Palette { name: format!("palette{}", project.palettes.len()), colors });
+            }
+        });
+
+        egui::CollapsingHeader::new(format!("Maps ({})", project.maps.len())).show(ui, |ui| {
+            for map in &project.maps {
+                if ui.button(&map.name).on_hover_text(format!("{}x{} tiles of {}", map.map.width, map.map.height, map.tileset)).clicked() {
+                    if let Some(doc) = project.document(&map.tileset) {
+                        open = Some(doc.clone());
+                    }
+                    self.tilemaps = vec![(map.name.clone(), map.map.clone())];
+                    self.selected_tilemap = 0;
+                    self.show_tilemap = true;
+                }
+            }
+
+            let tileset = self.tabs.get(self.active_tab).map(|t| t.name.clone());
+            let enabled = tileset.is_some() && !self.tilemaps.is_empty();
+            if ui.add_enabled(enabled, egui::Button::new("Add selected tilemap")).on_hover_text("indexing the active document").clicked() {
+                if let (Some(tileset), Some((name, map))) = (tileset, self.tilemaps.get(self.selected_tilemap)) {
+                    project.maps.push(MapDocument { name: name.clone(), tileset, map: map.clone() });
+                }
+            }
+        });
+
+        egui::CollapsingHeader::new(format!("Exports ({})", project.exports.len())).show(ui, |ui| {
+            let mut remove = None;
+            for (i, target) in project.exports.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} -> {} ({:?}, {})", target.document, target.path, target.format, target.compression.name()));
+                    if ui.small_button("x").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                project.exports.remove(i);
+            }
+
+            if let Some(tab) = self.tabs.get(self.active_tab) {
+                if ui.button(format!("Add raw export of {}", tab.name)).on_hover_text("uses the selected compression").clicked() {
+                    let extension = self.compression.extension().map(|e| format!(".{}", e)).unwrap_or_default();
+                    project.exports.push(ExportTarget {
+                        document: tab.name.clone(),
+                        path: format!("{}.2bpp{}", tab.name, extension),
+                        format: crate::convert::ExportFormat::Raw,
+                        dedupe: false,
+                        compression: self.compression
+                    });
+                }
+            }
+        });
+
+        if let Some(doc) = open {
+            let path = project::resolve(&project_path, &doc.path).display().to_string();
+            let palette = doc.palette.as_ref().and_then(|name| project.palette(name)).cloned();
+            self.open_tab(doc.name, path);
+            if let Some(palette) = palette {
+                set_palette(&mut self.palette, &palette.colors);
+            }
+            if !doc.animations.is_empty() {
+                self.animations = doc.animations;
+                self.selected_animation = 0;
+                self.selected_frame = 0;
+            }
+        }
+    }
+
+    /// writes the canvas to the recovery slot if it changed since it was last stored
+    fn autosave(&mut self, storage: &mut dyn eframe::Storage, now: f64)
+    {
+        // keep the slot until the user decided what to do with it
+        if self.recovery.is_some() || now - self.last_autosave < AUTOSAVE_INTERVAL || self.autosaved.as_ref() == Some(&self.sheet) {
+            return;
+        }
+        let recovery = Recovery { path: self.picked_path.clone(), format: self.format, sheet: self.sheet.clone() };
+        eframe::set_value(storage, RECOVERY_KEY, &Some(recovery));
+        storage.flush();
+        self.autosaved = Some(self.sheet.clone());
+        self.last_autosave = now;
+    }
+
+    fn recovery_ui(&mut self, ctx: &egui::Context)
+    {
+        let mut restore = None;
+        if let Some(recovery) = &self.recovery {
+            egui::Window::new("Restore unsaved work?").collapsible(false).resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!("rzrTiles didn't shut down cleanly, {}x{} pixels of {} weren't saved.",
+                    recovery.sheet.width, recovery.sheet.height,
+                    if recovery.path.is_empty() { "an untitled sheet" } else { recovery.path.as_str() }));
+                ui.horizontal(|ui| {
+                    if ui.button("Restore").clicked() {
+                        restore = Some(true);
+                    }
+                    if ui.button("Discard").clicked() {
+                        restore = Some(false);
+                    }
+                });
+            });
+        }
+
+        match restore {
+            Some(true) => if let Some(recovery) = self.recovery.take() {
+                self.sheet = recovery.sheet;
+                self.format = recovery.format;
+                self.picked_path = recovery.path;
+                // the saved copy belongs to the old path, it is re-read once the storage is ready
+                self.clean = None;
+                self.clean_pending = true;
+            },
+            // the next autosave overwrites the slot with the current canvas
+            Some(false) => {
+                self.recovery = None;
+                self.autosaved = None;
+            }
+            None => {}
+        }
+    }
+
+    fn import_image(&mut self, data: &[u8])
+    {
+        match image::load_from_memory(data) {
+            Ok(img) => {
+                self.image = img;
+                self.resample_image();
+            }
+            Err(err) => self.status.error(format!("load image: {}", err))
+        }
+    }
+
+    /// quantizes the imported image into the sheet with the current filter and resize mode
+    fn resample_image(&mut self)
+    {
+        let thumbnail = match self.resize_mode{
+            ResizeMode::Default => self.image.resize(self.sheet.width, self.sheet.height, self.filter_type).to_luma8(),
+            ResizeMode::Exact => self.image.resize_exact(self.sheet.width, self.sheet.height, self.filter_type).to_luma8(),
+            ResizeMode::Fill => self.image.resize_to_fill(self.sheet.width, self.sheet.height, self.filter_type).to_luma8(),
+            ResizeMode::Thumbnail => self.image.thumbnail(self.sheet.width, self.sheet.height).to_luma8(),
+            ResizeMode::ThumbnailExact => self.image.thumbnail_exact(self.sheet.width, self.sheet.height).to_luma8(),
+        };
+
+        for y in 0..std::cmp::min( self.sheet.height, thumbnail.height() ) {
+            for x in 0..std::cmp::min(self.sheet.width, thumbnail.width() ) {
+                let color = thumbnail.get_pixel(x, y);
+                self.set(x, y, quantize_luma_to(color[0], self.colors()));
+            }
+        }
+    }
+
+    /// tiles in the selected format and compression
+    fn export_raw(&self)
+    {
+        let mut file_name = format!("tiles.{}", self.format.extension());
+        let extension = self.compression.extension().unwrap_or(self.format.extension());
+        if extension != self.format.extension() {
+            file_name = format!("{}.{}", file_name, extension);
+        }
+
+        let mut data = self.sheet.export_as(self.format);
+        if self.format == TileFormat::Nes {
+            pad_to_chr_banks(&mut data);
+        }
+        self.files.save(&file_name, self.format.name(), &[extension], &self.compression.compress(&data));
+    }
+
+    fn file_menu_ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame)
+    {
+        let ctx = ui.ctx().clone();
+        if ui.add(egui::Button::new("New…").shortcut_text(ctx.format_shortcut(&SHORTCUT_NEW))).clicked() {
+            self.new_sheet = Some((16, 8, self.sheet.mode));
+            ui.close_menu();
+        }
+        if ui.add(egui::Button::new("Open…").shortcut_text(ctx.format_shortcut(&SHORTCUT_OPEN))).clicked() {
+            ui.close_menu();
+            self.open_dialog(&ctx);
+        }
+        ui.menu_button("Open Recent", |ui| {
+            self.recent_files_ui(ui);
+        });
+        ui.separator();
+
+        if ui.add(egui::Button::new("Save").shortcut_text(ctx.format_shortcut(&SHORTCUT_SAVE))).clicked() {
+            ui.close_menu();
+            self.save_document();
+        }
+        if ui.add(egui::Button::new("Save As…").shortcut_text(ctx.format_shortcut(&SHORTCUT_SAVE_AS))).clicked() {
+            ui.close_menu();
+            self.save_document_as();
+        }
+        if ui.add_enabled(self.is_dirty(), egui::Button::new("Revert")).on_hover_text("drop the unsaved changes").clicked() {
+            ui.close_menu();
+            self.revert();
+        }
+        ui.separator();
+
+        ui.menu_button("Import", |ui| {
+            if ui.button("Image…").on_hover_text("resampled into the sheet with the filter and resize mode").clicked() {
+                ui.close_menu();
+                self.files.pick(&ctx, FileAction::Image, "Image", &["png", "jpg", "jpeg", "gif", "bmp"]);
+            }
+            if ui.button("Raw tiles…").on_hover_text("asks for the format and layout").clicked() {
+                ui.close_menu();
+                self.files.pick(&ctx, FileAction::Raw, "Raw tiles", &["2bpp", "chr", "4bpp", "bin"]);
+            }
+            if ui.button("VRAM dump…").on_hover_text("8KB/16KB VRAM dump or BESS save state").clicked() {
+                ui.close_menu();
+                self.files.pick(&ctx, FileAction::Vram, "", &[]);
+            }
+            if ui.button("Aseprite…").on_hover_text("indexed sprite, frames are stacked top to bottom").clicked() {
+                ui.close_menu();
+                self.files.pick(&ctx, FileAction::Aseprite, "Aseprite", &["aseprite", "ase"]);
+            }
+            // the tileset is read from next to the map
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui.button("Tiled map…").on_hover_text(".tmx or .tmj map with its tileset").clicked() {
+                ui.close_menu();
+                if let Some(path) = rfd::FileDialog::new().add_filter("Tiled map", &["tmx", "tmj", "json"]).pick_file() {
+                    self.import_tiled(path);
+                }
+            }
+        });
+
+        ui.menu_button("Export", |ui| {
+            if ui.button(format!("Raw {} tiles…", self.format.name())).on_hover_text("with the selected compression").clicked() {
+                ui.close_menu();
+                self.export_raw();
+            }
+            if ui.button("Test ROM…").on_hover_text("bootable .gb showing the tiles").clicked() {
+                ui.close_menu();
+                self.export_test_rom();
+            }
+            // writes the tileset next to the map
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui.button("Tiled map…").on_hover_text("map as .tmx or .tmj, tileset as .tsx and .png next to it").clicked() {
+                ui.close_menu();
+                if let Some(path) = rfd::FileDialog::new().add_filter("Tiled map", &["tmx", "tmj"]).set_file_name("tiles.tmx").save_file() {
+                    self.export_tiled(path);
+                }
+            }
+            if ui.button("GB Studio…").clicked() {
+                ui.close_menu();
+                self.show_gbstudio = true;
+            }
+        });
+        if ui.button("Copy share link").on_hover_text("link to the web editor with this sheet and palette").clicked() {
+            ui.close_menu();
+            if let Some(link) = self.share_link() {
+                ctx.output_mut(|o| o.copied_text = link);
+                self.status.info("share link copied");
+            }
+        }
+        ui.separator();
+
+        ui.checkbox(&mut self.reopen_last, "Reopen last file on startup");
+
+        #[cfg(not(target_arch = "wasm32"))] // no File->Quit on web pages!
+        if ui.button("Quit").clicked() {
+            _frame.close();
+        }
+    }
+
+    /// notice above the menu bar when a new version of the web build is ready
+    fn update_ui(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame)
+    {
+        if self.update_dismissed || !update_available() {
+            return;
+        }
+        egui::TopBottomPanel::top("update_panel").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("A new version of rzrTiles is available.");
+                if ui.button("Reload").on_hover_text("the edited sheet is kept, other tabs are closed").clicked() {
+                    if self.unsaved_documents().is_empty() {
+                        self.reload(_frame);
+                    } else {
+                        self.close_prompt = Some(ClosePrompt::Reload);
+                    }
+                }
+                if ui.button("Later").clicked() {
+                    self.update_dismissed = true;
+                }
+            });
+        });
+    }
+
+    /// the latest message below the canvas, earlier ones on hover
+    fn status_ui(&self, ctx: &egui::Context)
+    {
+        let messages = self.status.messages();
+        let latest = match messages.last() {
+            Some(latest) => latest,
+            None => return
+        };
+        egui::TopBottomPanel::bottom("status_panel").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let color = if latest.error { ui.visuals().error_fg_color } else { ui.visuals().text_color() };
+                let label = ui.colored_label(color, &latest.text);
+                if messages.len() > 1 {
+                    let earlier: Vec<&str> = messages.iter().rev().skip(1).map(|m| m.text.as_str()).collect();
+                    label.on_hover_text(earlier.join("\n"));
+                }
+                if ui.small_button("x").on_hover_text("clear").clicked() {
+                    self.status.clear();
+                }
+            });
+        });
+    }
+
+    /// saves the app state and switches to the new version
+    fn reload(&mut self, frame: &mut eframe::Frame)
+    {
+        // the reloaded page starts from the saved app state
+        if let Some(storage) = frame.storage_mut() {
+            eframe::App::save(self, storage);
+            storage.flush();
+        }
+        #[cfg(target_arch = "wasm32")]
+        if let Err(err) = apply_update() {
+            self.status.error(format!("update: {}", err));
+        }
+    }
+
+    fn file_shortcuts(&mut self, ctx: &egui::Context)
+    {
+        // Save As first, Ctrl+S would also match Ctrl+Shift+S
+        let (new, open, save_as, save) = ctx.input_mut(|i| (
+            i.consume_shortcut(&SHORTCUT_NEW),
+            i.consume_shortcut(&SHORTCUT_OPEN),
+            i.consume_shortcut(&SHORTCUT_SAVE_AS),
+            i.consume_shortcut(&SHORTCUT_SAVE)
+        ));
+        if new {
+            self.new_sheet = Some((16, 8, self.sheet.mode));
+        }
+        if open {
+            self.open_dialog(ctx);
+        }
+        if save_as {
+            self.save_document_as();
+        } else if save {
+            self.save_document();
+        }
+    }
+
+    fn new_sheet_ui(&mut self, ctx: &egui::Context)
+    {
+        let (mut width, mut height, mut mode) = match self.new_sheet {
+            Some(size) => size,
+            None => return
+        };
+
+        let mut create = false;
+        let mut cancel = false;
+        egui::Window::new("New sheet").collapsible(false).resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            egui::Grid::new("new_sheet").show(ui, |ui| {
+                ui.label("Width");
+                ui.add(egui::DragValue::new(&mut width).clamp_range(1..=64).suffix(" tiles"));
+                ui.end_row();
+                ui.label("Height");
+                ui.add(egui::DragValue::new(&mut height).clamp_range(1..=64).suffix(" tiles"));
+                ui.end_row();
+                ui.label("Mode");
+                egui::ComboBox::from_id_source("new_sheet_mode")
+                    .selected_text(format!("{:?}", mode))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut mode, TileMode::Y8, "8x8");
+                        ui.selectable_value(&mut mode, TileMode::Y16, "8x16");
+                    });
+                ui.end_row();
+            });
+            ui.label(format!("{}x{} pixels", width * 8, height * mode as u32));
+            ui.horizontal(|ui| {
+                create = ui.button("Create").clicked();
+                cancel = ui.button("Cancel").clicked();
+            });
+        });
+
+        self.new_sheet = Some((width, height, mode));
+        if create {
+            self.new_document(width, height, mode);
+        }
+        if create || cancel {
+            self.new_sheet = None;
+        }
+    }
+
+    /// documents and projects saved in the web build's storage
+    fn browser_ui(&mut self, ctx: &egui::Context)
+    {
+        let browser = match &mut self.browser {
+            Some(browser) => browser,
+            None => return
+        };
+
+        let mut open = true;
+        let mut save = false;
+        let mut upload = false;
+        let mut load = None;
+        let mut download = None;
+        let mut delete = None;
+        egui::Window::new("Documents").open(&mut open).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut browser.name);
+                save = ui.add_enabled(!browser.name.trim().is_empty(), egui::Button::new("Save"))
+                    .on_hover_text("the edited sheet under this name").clicked();
+            });
+            ui.separator();
+
+            let names = self.storage.list();
+            if names.is_empty() {
+                ui.label("No saved documents");
+            }
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                for name in names {
+                    ui.horizontal(|ui| {
+                        if ui.selectable_label(name == self.picked_path || name == self.project_path, &name).on_hover_text("open").clicked() {
+                            load = Some(name.clone());
+                        }
+                        if ui.small_button("⬇").on_hover_text("download").clicked() {
+                            download = Some(name.clone());
+                        }
+                        if browser.delete.as_ref() == Some(&name) {
+                            if ui.small_button("Delete").clicked() {
+                                delete = Some(name.clone());
+                            }
+                            if ui.small_button("Keep").clicked() {
+                                browser.delete = None;
+                            }
+                        } else if ui.small_button("x").on_hover_text("delete").clicked() {
+                            browser.delete = Some(name.clone());
+                        }
+                    });
+                }
+            });
+            ui.separator();
+            upload = ui.button("Upload…").on_hover_text("open a .tl file from your disk").clicked();
+        });
+
+        if save {
+            let name = browser.name.trim().to_owned();
+            self.save_document_to(if name.contains('.') { name } else { format!("{}.tl", name) });
+        }
+        if upload {
+            self.files.pick(ctx, FileAction::Open, "rzrTiles", &["tl"]);
+        }
+        if let Some(name) = download {
+            match self.storage.read(&name) {
+                Ok(data) => {
+                    self.files.write(&name, &data);
+                }
+                Err(err) => self.status.error(format!("download {}: {}", name, err))
+            }
+        }
+        if let Some(name) = delete {
+            if let Err(err) = self.storage.remove(&name) {
+                self.status.error(format!("delete {}: {}", name, err));
+            }
+            if let Some(browser) = &mut self.browser {
+                browser.delete = None;
+            }
+        }
+        if let Some(name) = load {
+            if name.ends_with(".json") {
+                match self.storage.read(&name) {
+                    Ok(data) => self.open_picked(FileAction::Project, PickedFile { name, path: None, data }),
+                    Err(err) => self.status.error(format!("open project: {}", err))
+                }
+            } else {
+                self.open_tab(document_name(&name), name);
+            }
+            open = false;
+        }
+        if !open {
+            self.browser = None;
         }
     }
-}
 
-impl TemplateApp {
-    /// Called once before the first frame.
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        // This is also where you can customize the look and feel of egui using
-        // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
+    /// shown when closing was cancelled because of unsaved changes
+    fn close_prompt_ui(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame)
+    {
+        let prompt = match self.close_prompt {
+            Some(prompt) => prompt,
+            None => return
+        };
 
-        // Load previous app state (if any).
-        // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        let (unsaved, save_label, discard_label) = match prompt {
+            ClosePrompt::Quit => (self.unsaved_documents(), "Save all and quit", "Quit without saving"),
+            ClosePrompt::Reload => (self.unsaved_documents(), "Save all and reload", "Reload without saving"),
+            ClosePrompt::Tab(i) => match self.tabs.get(i) {
+                Some(tab) => (vec![tab.name.clone()], "Save and close", "Close without saving"),
+                None => {
+                    self.close_prompt = None;
+                    return;
+                }
+            }
+        };
+        let mut choice = None;
+        egui::Window::new("Unsaved changes").collapsible(false).resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label(if unsaved.len() == 1 { "This document has unsaved changes:" } else { "These documents have unsaved changes:" });
+            for name in &unsaved {
+                ui.label(format!("  {}", name));
+            }
+            ui.horizontal(|ui| {
+                if ui.button(save_label).clicked() {
+                    choice = Some(true);
+                }
+                if ui.button(discard_label).clicked() {
+                    choice = Some(false);
+                }
+                if ui.button("Cancel").clicked() {
+                    self.close_prompt = None;
+                }
+            });
+        });
+
+        let save = match choice {
+            Some(save) => save,
+            None => return
+        };
+        self.close_prompt = None;
+        match prompt {
+            ClosePrompt::Quit | ClosePrompt::Reload => {
+                if save {
+                    self.save_all();
+                }
+                // documents that still have changes were cancelled in the save dialog
+                if save && !self.unsaved_documents().is_empty() {
+                    return;
+                }
+                if prompt == ClosePrompt::Reload {
+                    self.reload(frame);
+                } else {
+                    self.allowed_to_close = true;
+                    frame.close();
+                }
+            }
+            ClosePrompt::Tab(i) => {
+                if save {
+                    let active = self.active_tab;
+                    self.switch_tab(i);
+                    self.save_document();
+                    // cancelled in the save dialog, or named in the document browser first on the web
+                    if self.tab_dirty(i) {
+                        return;
+                    }
+                    self.switch_tab(active);
+                }
+                self.remove_tab(i);
+            }
         }
+    }
 
-        Default::default()
+    /// saves every document with unsaved changes, asking for a file for untitled ones
+    fn save_all(&mut self)
+    {
+        if self.tabs.is_empty() {
+            self.save_document();
+            return;
+        }
+        let active = self.active_tab;
+        for i in 0..self.tabs.len() {
+            if self.tab_dirty(i) {
+                self.switch_tab(i);
+                self.save_document();
+            }
+        }
+        self.switch_tab(active);
     }
 
-    pub fn get(&self, x: u32, y: u32) -> u8
+    pub fn tile_count(&self) -> u32
     {
-        let index = (y*self.width+x) as usize;
-        self.pixels[index]
+        self.sheet.tile_count()
     }
-    pub fn set(&mut self, x: u32, y: u32, val: u8)
+
+    /// top left pixel of tile t, tiles are numbered in export order
+    pub fn tile_origin(&self, t: u32) -> Option<(u32, u32)>
     {
-        let index = (y*self.width+x) as usize;
-        self.pixels[index] = val;
+        self.sheet.tile_origin(t)
     }
 
-    pub fn export(&self) -> Vec<u8>
+    /// current and previous frame of the selected animation for onion-skinning
+    fn onion_frames(&self) -> Option<(AnimFrame, AnimFrame)>
     {
-        pixels_to_gb_tiles(&self.pixels, self.width, self.height, self.mode)
+        let anim = self.animations.get(self.selected_animation)?;
+        let len = anim.frames.len();
+        if !self.onion_skin || len < 2 || self.selected_frame >= len {
+            return None;
+        }
+        Some((anim.frames[self.selected_frame], anim.frames[(self.selected_frame + len - 1) % len]))
     }
 
-    /// w and h number of tiles
-    pub fn import(&mut self, data: &[u8], w: u32, h: u32)
+    /// regenerates the hex view only when the sheet changed since it was last printed
+    fn update_hex(&mut self)
     {
-        self.width = w*8;
-        self.height = h*8;
-        self.pixels = gb_tiles_to_pixels(data, w, h, self.mode);
+        if self.hex_sheet.as_ref() == Some(&self.sheet) {
+            return;
+        }
+
+        self.hex_str.clear();
+        for row in self.sheet.pixels.chunks_exact(self.sheet.width as usize) {
+            for (i, pixels) in row.chunks_exact(8).enumerate() {
+                let (left, right) = encode_row(pixels);
+                if i > 0 {
+                    self.hex_str.push(' ');
+                }
+                let _ = write!(self.hex_str, "{:02X} {:02X}", left, right);
+            }
+            self.hex_str.push('\n');
+        }
+        self.hex_sheet = Some(self.sheet.clone());
     }
 
-    pub fn save_to_disk(&self, path: impl Into<String>)
+    /// recompresses only when the sheet or format changed since the sizes were computed
+    fn update_compressed_sizes(&mut self)
     {
-        let file = match File::create(path.into())
-        {
-            Ok(file) => file,
-            Err(err) => 
-            {
-                // TODO log error
-                println!("save_to_disk: {}", err);
+        if let Some(computed) = &self.compressed_sizes {
+            if computed.sheet == self.sheet && computed.format == self.format {
                 return;
             }
-        };
+        }
 
-        let mut f = BufWriter::new( file );
-        f.write(TL_MAGIC);
-        f.write(TL_VERSION);
-        f.write(&[self.mode as u8]);
-        // TODO: need to divide self.height by self.mode?
-        let dims =[(self.width / 8) as u8, (self.height / 8) as u8];
-        f.write(&dims);
-        let data = pixels_to_gb_tiles(&self.pixels, self.width, self.height, self.mode);
-        f.write(&data);
+        let data = self.sheet.export_as(self.format);
+        let sizes = Compression::ALL.iter().map(|c| (*c, c.compress(&data).len())).collect();
+        self.compressed_sizes = Some(CompressedSizes { sheet: self.sheet.clone(), format: self.format, sizes });
     }
 
-    pub fn load_from_disk(&mut self, path: impl Into<String>)
+    fn paint_frame(&self, ui: &mut egui::Ui, frame: &AnimFrame, pixel_size: f32)
     {
-        let file = match File::open(path.into())
-        {
-            Ok(file) => file,
-            Err(err) => 
-            {
-                // TODO log error
-                println!("load_from_disk: {}", err);
+        let ystep = self.sheet.mode as u32;
+        let columns = std::cmp::max(frame.columns, 1);
+        let size = Vec2::new((columns * 8) as f32, (frame.rows() * ystep) as f32) * pixel_size;
+        let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, self.palette[0]);
+
+        for k in 0..frame.tile_count {
+            if let Some((tx, ty)) = self.tile_origin(frame.first_tile + k) {
+                let offset = rect.min + Vec2::new(((k % columns) * 8) as f32, ((k / columns) * ystep) as f32) * pixel_size;
+                for y in 0..ystep {
+                    for x in 0..8 {
+                        let i = self.get(tx + x, ty + y) % self.colors();
+                        let min = offset + Vec2::new(x as f32, y as f32) * pixel_size;
+                        let pixel = egui::Rect::from_min_size(min, Vec2::splat(pixel_size));
+                        painter.rect_filled(pixel, 0.0, self.palette[i as usize]);
+                    }
+                }
+            }
+        }
+    }
+
+    fn animation_ui(&mut self, ui: &mut egui::Ui)
+    {
+        ui.horizontal(|ui| {
+            let selected_name = self.animations.get(self.selected_animation).map(|a| a.name.clone()).unwrap_or_default();
+            egui::ComboBox::from_id_source("animation")
+                .selected_text(selected_name)
+                .show_ui(ui, |ui| {
+                    for (i, anim) in self.animations.iter().enumerate() {
+                        ui.selectable_value(&mut self.selected_animation, i, &anim.name);
+                    }
+                });
+
+            if ui.button("New").clicked() {
+                self.animations.push(Animation::new(format!("anim{}", self.animations.len())));
+                self.selected_animation = self.animations.len() - 1;
+                self.selected_frame = 0;
+            }
+
+            if ui.button("Delete").clicked() && self.selected_animation < self.animations.len() {
+                self.animations.remove(self.selected_animation);
+                self.selected_animation = self.selected_animation.saturating_sub(1);
+                self.selected_frame = 0;
+            }
+        });
+
+        let max_tile = self.tile_count().saturating_sub(1);
+        let anim = match self.animations.get_mut(self.selected_animation) {
+            Some(anim) => anim,
+            None => {
+                ui.label("No animation, press New to add one.");
                 return;
             }
         };
 
-        let mut f = BufReader::new( file );
+        ui.horizontal(|ui| {
+            ui.label("Name:");
+            ui.text_edit_singleline(&mut anim.name);
+        });
 
-        // Header is 8 byte in size
-        let mut magic: [u8;4] = [0,0,0,0];
-        let mut version: [u8;1] = [0];
-        let mut mode: [u8;1] = [0];
-        let mut dims: [u8;2] = [0,0];
+        let mut remove = None;
+        egui::Grid::new("frames").striped(true).show(ui, |ui| {
+            ui.label("#");
+            ui.label("First tile");
+            ui.label("Tiles");
+            ui.label("Columns");
+            ui.label("Duration");
+            ui.end_row();
+
+            for (i, frame) in anim.frames.iter_mut().enumerate() {
+                ui.radio_value(&mut self.selected_frame, i, i.to_string());
+                ui.add(egui::DragValue::new(&mut frame.first_tile).clamp_range(0..=max_tile));
+                ui.add(egui::DragValue::new(&mut frame.tile_count).clamp_range(1..=max_tile + 1));
+                ui.add(egui::DragValue::new(&mut frame.columns).clamp_range(1..=16));
+                ui.add(egui::DragValue::new(&mut frame.duration).clamp_range(1..=255).suffix(" f"));
+                if ui.small_button("x").clicked() {
+                    remove = Some(i);
+                }
+                ui.end_row();
+            }
+        });
 
-        f.read_exact(&mut magic);
-        f.read_exact(&mut version);
-        f.read_exact(&mut mode);
-        f.read_exact(&mut dims);
+        if let Some(i) = remove {
+            anim.frames.remove(i);
+        }
 
-        if magic == TL_MAGIC && version == TL_VERSION
-        {
-            let w:u32 = dims[0] as u32;
-            let h:u32 = dims[1] as u32;
+        ui.horizontal(|ui| {
+            if ui.button("Add frame").clicked() {
+                let mut frame = anim.frames.last().copied().unwrap_or_default();
+                frame.first_tile = std::cmp::min(frame.first_tile + frame.tile_count, max_tile);
+                anim.frames.push(frame);
+            }
 
-            if mode[0] == (TileMode::Y16 as u8) {
-                self.mode = TileMode::Y16;
-            } else {
-                self.mode = TileMode::Y8;
+            let total = anim.total_duration();
+            ui.label(format!("{} GB frames ({:.2}s)", total, total as f64 / crate::animation::GB_FPS));
+        });
+
+        if self.selected_frame >= anim.frames.len() {
+            self.selected_frame = anim.frames.len().saturating_sub(1);
+        }
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.playing, "Play");
+            ui.checkbox(&mut self.onion_skin, "Onion skin");
+
+            if ui.button("Export frame table").clicked() {
+                self.files.save(&format!("{}.inc", anim.name), "Assembly", &["inc", "asm", "s"], anim.frame_table().as_bytes());
             }
+        });
 
-            let bytes_per_tile = self.mode as u32 * 2;
-            let mut tiles: Vec<u8> = vec![0; (w*h*bytes_per_tile) as usize ];
-            f.read_exact(&mut tiles);
+        let anim = self.animations[self.selected_animation].clone();
+        let shown = if self.playing {
+            ui.ctx().request_repaint();
+            anim.frame_at_time(ui.input(|i| i.time))
+        } else {
+            Some(self.selected_frame)
+        };
 
-            self.pixels = gb_tiles_to_pixels(&tiles, w as u32, h as u32, self.mode);
-            self.width = w * 8;
-            self.height = h * 8;
-        }      
+        if let Some(frame) = shown.and_then(|i| anim.frames.get(i)) {
+            self.paint_frame(ui, frame, 4.0 * std::cmp::max(self.scale as u32, 1) as f32);
+        }
     }
 
-}
+    fn ripper_ui(&mut self, ui: &mut egui::Ui)
+    {
+        ui.horizontal(|ui| {
+            if ui.button("Open ROM/binary").clicked() {
+                self.files.pick(ui.ctx(), FileAction::Rom, "ROM", &["gb", "gbc", "bin"]);
+            }
 
-/// w and h number of tiles
-pub fn gb_tiles_to_pixels(data: &[u8], w: u32, h: u32, mode: TileMode) -> Vec<u8>
-{
-    let ystep = mode as u32;
-    assert_eq!(data.len(), (w*h*ystep*2) as usize); // 2 bytes per row, 8/16 rows per tile
+            if let Some(ripper) = &self.ripper {
+                ui.label(format!("{} bytes, {} tiles", ripper.data.len(), ripper.tile_count()));
+            }
+        });
+
+        let ripper = match self.ripper.as_mut() {
+            Some(ripper) => ripper,
+            None => {
+                ui.label("Open a file to browse its tiles.");
+                return;
+            }
+        };
+
+        ui.horizontal(|ui| {
+            let last = ripper.data.len().saturating_sub(1);
+            let bytes_per_tile = ripper.bytes_per_tile();
+            ui.label("Offset:");
+            ui.add(egui::DragValue::new(&mut ripper.offset).clamp_range(0..=last).hexadecimal(6, false, true).prefix("$"));
+            if ui.small_button("-1").clicked() {
+                ripper.offset = ripper.offset.saturating_sub(1);
+            }
+            if ui.small_button("+1").clicked() {
+                ripper.offset = std::cmp::min(ripper.offset + 1, last);
+            }
+            if ui.small_button("-tile").clicked() {
+                ripper.offset = ripper.offset.saturating_sub(bytes_per_tile);
+            }
+            if ui.small_button("+tile").clicked() {
+                ripper.offset = std::cmp::min(ripper.offset + bytes_per_tile, last);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut ripper.tiles_per_row).clamp_range(1..=32).suffix(" tiles wide"));
+            egui::ComboBox::from_id_source("rip_format")
+                .selected_text(ripper.format.name())
+                .show_ui(ui, |ui| {
+                    for format in TileFormat::ALL {
+                        ui.selectable_value(&mut ripper.format, format, format.name());
+                    }
+                });
+            egui::ComboBox::from_id_source("rip_mode")
+                .selected_text(format!("{:?}", ripper.mode))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut ripper.mode, TileMode::Y8, "8x8");
+                    ui.selectable_value(&mut ripper.mode, TileMode::Y16, "8x16");
+                });
+        });
+
+        let max_tile = ripper.tile_count().saturating_sub(1);
+        self.rip_first = std::cmp::min(self.rip_first, max_tile);
+        self.rip_count = self.rip_count.clamp(1, max_tile + 1 - self.rip_first);
+        ui.horizontal(|ui| {
+            ui.label("Tiles:");
+            ui.add(egui::DragValue::new(&mut self.rip_first).clamp_range(0..=max_tile));
+            ui.add(egui::DragValue::new(&mut self.rip_count).clamp_range(1..=max_tile + 1 - self.rip_first).prefix("count "));
+            ui.label(format!("${:06X}-${:06X}", ripper.tile_address(self.rip_first), ripper.tile_address(self.rip_first + self.rip_count) - 1));
+        });
+
+        if ui.button("Extract to editor").on_hover_text("click a tile to start the range, shift-click to end it").clicked() {
+            self.sheet = ripper.extract(self.rip_first, self.rip_count);
+            self.format = ripper.format;
+        }
 
-    let height = h*ystep;
-    let width = w*8;
-    let mut pixels: Vec<u8> = vec![0; (width*height) as usize];
-    let mut trow = 0;
+        // only the visible rows are decoded, so large files scroll smoothly
+        let pixel_size = 2.0 * std::cmp::max(self.scale as u32, 1) as f32;
+        let tiles_per_row = ripper.tiles_per_row;
+        let ystep = ripper.mode as u32;
+        let row_height = ystep as f32 * pixel_size;
+        let label_width = 64.0;
+        ui.spacing_mut().item_spacing.y = 0.0;
+        egui::ScrollArea::vertical().max_height(480.0).auto_shrink([false, true]).show_rows(ui, row_height, ripper.rows() as usize, |ui, range| {
+            let first_row = range.start as u32;
+            let sheet = ripper.rows_sheet(first_row, range.len() as u32);
+            let size = Vec2::new(label_width + (tiles_per_row * 8) as f32 * pixel_size, range.len() as f32 * row_height);
+            let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+            let painter = ui.painter_at(rect);
+            let grid = rect.min + Vec2::new(label_width, 0.0);
+
+            for r in 0..range.len() as u32 {
+                let address = ripper.tile_address((first_row + r) * tiles_per_row);
+                let pos = rect.min + Vec2::new(0.0, r as f32 * row_height);
+                painter.text(pos, egui::Align2::LEFT_TOP, format!("${:06X}", address), egui::FontId::monospace(10.0), ui.visuals().text_color());
+            }
+
+            let colors = ripper.format.colors() as u8;
+            painter.rect_filled(egui::Rect::from_min_size(grid, Vec2::new(sheet.width as f32, sheet.height as f32) * pixel_size), 0.0, self.palette[0]);
+            for y in 0..sheet.height {
+                for x in 0..sheet.width {
+                    let i = sheet.get(x, y) % colors;
+                    if i != 0 {
+                        let min = grid + Vec2::new(x as f32, y as f32) * pixel_size;
+                        painter.rect_filled(egui::Rect::from_min_size(min, Vec2::splat(pixel_size)), 0.0, self.palette[i as usize]);
+                    }
+                }
+            }
+
+            // outline the selected range
+            let tile_size = Vec2::new(8.0, ystep as f32) * pixel_size;
+            let visible = first_row * tiles_per_row..(first_row + range.len() as u32) * tiles_per_row;
+            for t in std::cmp::max(self.rip_first, visible.start)..std::cmp::min(self.rip_first + self.rip_count, visible.end) {
+                let k = t - visible.start;
+                let min = grid + Vec2::new((k % tiles_per_row) as f32, (k / tiles_per_row) as f32) * tile_size;
+                painter.rect_stroke(egui::Rect::from_min_size(min, tile_size), 0.0, Stroke::new(1.0, Color32::from_rgb(230, 110, 0)));
+            }
 
-    for y in 0..h {
-        for x in 0..w {
-            for j in 0..ystep { // y_tile
-                let left = data[trow];trow += 1;
-                let right = data[trow];trow += 1;
-                for i in 0..8 { // x_tile
-                    let color = ((left >> (7-i)) & 0b1) | ((right >> (7-i)) & 0b1) << 1;
-                    let pixel = (y*8+j)*(w*8)+(x*8)+i;
-                    pixels[pixel as usize] = color;
+            if let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked()) {
+                let cell = (pos - grid) / tile_size;
+                if cell.x >= 0.0 && (cell.x as u32) < tiles_per_row {
+                    let t = std::cmp::min(visible.start + cell.y as u32 * tiles_per_row + cell.x as u32, max_tile);
+                    if ui.input(|i| i.modifiers.shift) && t >= self.rip_first {
+                        self.rip_count = t - self.rip_first + 1;
+                    } else {
+                        self.rip_first = t;
+                        self.rip_count = 1;
+                    }
                 }
             }
+        });
+    }
+
+    fn import_vram(&mut self, data: &[u8])
+    {
+        match vram::import(data, self.vram_addressing) {
+            Ok(vram) => {
+                self.sheet = vram.tileset;
+                self.format = TileFormat::Gb;
+                let [low, high] = vram.maps;
+                self.tilemaps = vec![("$9800".to_owned(), low), ("$9C00".to_owned(), high)];
+                // LCDC bit 3 selects the BG map
+                self.selected_tilemap = vram.lcdc.map_or(0, |lcdc| (lcdc >> 3 & 1) as usize);
+                self.show_tilemap = true;
+            }
+            Err(err) => self.status.error(format!("import vram: {}", err))
+        }
+    }
+
+    /// frames stacked in the sheet with the sprite's palette, tags or multiple frames become animations
+    fn import_aseprite(&mut self, file_name: &str, data: &[u8])
+    {
+        let ase = match aseprite::read_aseprite(data) {
+            Ok(ase) => ase,
+            Err(err) => {
+                self.status.error(format!("import aseprite: {}", err));
+                return;
+            }
+        };
+
+        self.sheet = ase.to_sheet(self.sheet.mode);
+        // the indices are used as-is, so the sprite's colors replace the palette
+        for (color, rgba) in self.palette.iter_mut().zip(&ase.palette) {
+            *color = Color32::from_rgb(rgba[0], rgba[1], rgba[2]);
+        }
+
+        if ase.frames.len() > 1 || !ase.tags.is_empty() {
+            let name = document_name(file_name);
+            self.selected_animation = self.animations.len();
+            self.selected_frame = 0;
+            self.animations.extend(ase.animations(&name, self.sheet.mode));
+            self.show_animation = true;
         }
     }
 
-    assert_eq!(pixels.len(), (w*h*ystep*8) as usize); // 64/128 pixel per tile (8x8/8x16)
+    /// the selected tilemap, or the sheet as laid out in the editor without one
+    fn export_map(&self) -> Tilemap
+    {
+        match self.tilemaps.get(self.selected_tilemap) {
+            Some((_, map)) => map.clone(),
+            None => Tilemap::from_sheet(&self.sheet)
+        }
+    }
 
-    pixels
-}
+    #[cfg(not(target_arch = "wasm32"))]
+    fn import_tiled(&mut self, path: std::path::PathBuf)
+    {
+        match tiled::import(&path) {
+            Ok((tileset, map)) => {
+                self.sheet = tileset;
+                self.format = TileFormat::Gb;
+                let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                self.tilemaps = vec![(name, map)];
+                self.selected_tilemap = 0;
+                self.show_tilemap = true;
+            }
+            Err(err) => self.status.error(format!("import tiled: {}", err))
+        }
+    }
 
-// w and h in number of pixels
-pub fn pixels_to_gb_tiles(data: &[u8], w: u32, h: u32, mode: TileMode) -> Vec<u8>
-{
-    assert_eq!(data.len(), (w*h) as usize);
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_tiled(&self, path: impl AsRef<std::path::Path>)
+    {
+        if let Err(err) = tiled::export(&self.sheet, &self.export_map(), path) {
+            self.status.error(format!("export tiled: {}", err));
+        }
+    }
+
+    /// ROM showing the selected tilemap, or the sheet as laid out in the editor without one
+    fn export_test_rom(&self)
+    {
+        // BGP maps the first four colors to the nearest DMG shades
+        let lumas = [0, 1, 2, 3].map(|i| {
+            let c = self.palette[i];
+            ((c.r() as u32 * 299 + c.g() as u32 * 587 + c.b() as u32 * 114) / 1000) as u8
+        });
+
+        match rom::test_rom(&self.sheet, &self.export_map(), rom::bgp_from_luma(lumas)) {
+            Ok(data) => {
+                self.files.save("tiles.gb", "Game Boy ROM", &["gb"], &data);
+            }
+            Err(err) => self.status.error(format!("export test rom: {}", err))
+        }
+    }
+
+    fn gbstudio_ui(&mut self, ui: &mut egui::Ui)
+    {
+        ui.horizontal(|ui| {
+            let kind = self.gbstudio_kind;
+            egui::ComboBox::from_id_source("gbstudio_kind")
+                .selected_text(kind.name())
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.gbstudio_kind, AssetKind::Background, AssetKind::Background.name());
+                    ui.selectable_value(&mut self.gbstudio_kind, AssetKind::Sprite, AssetKind::Sprite.name());
+                });
+            if kind != self.gbstudio_kind {
+                self.gbstudio_violations = None;
+            }
+            ui.label(format!("{} unique 8x8 tiles", gbstudio::unique_tiles(&self.sheet)));
+        });
 
-    let ystep = mode as u32;
-    let num_bytes = (w/4)*h;
+        ui.horizontal(|ui| {
+            if ui.button("Validate").clicked() {
+                self.gbstudio_violations = Some(gbstudio::validate(&self.sheet, self.gbstudio_kind));
+            }
 
-    let mut tiles: Vec<u8> = Vec::with_capacity(num_bytes as usize);
-    for y_tile in 0..h/ystep {
-        for x_tile in 0..w/8 {
-            for y in 0..ystep {
-                let mut left: u8 = 0;
-                let mut right: u8 = 0;
-    
-                for x in 0..8 {
-                    let tile = (y_tile*w*ystep)+y*w+x_tile*8+x;
-                    let cur = data[ tile as usize];
-                    left |= (cur & 0b01) << (7-x);
-                    right |= ( ( cur & 0b10 ) >> 1 ) << (7-x);
+            if ui.button("Export PNG").on_hover_text("validates first, nothing is written if a rule is broken").clicked() {
+                let violations = gbstudio::validate(&self.sheet, self.gbstudio_kind);
+                if violations.is_empty() {
+                    match gbstudio::export_png(&self.sheet, self.gbstudio_kind) {
+                        Ok(png) => {
+                            self.files.save(&format!("{}.png", self.gbstudio_kind.name().replace(' ', "_")), "PNG", &["png"], &png);
+                        }
+                        Err(err) => self.status.error(format!("export gb studio: {}", err))
+                    }
                 }
-                tiles.push(left);
-                tiles.push(right);
+                self.gbstudio_violations = Some(violations);
+            }
+        });
+
+        match &self.gbstudio_violations {
+            Some(violations) if violations.is_empty() => {
+                ui.colored_label(Color32::from_rgb(0, 160, 60), format!("valid GB Studio {}", self.gbstudio_kind.name()));
+            }
+            Some(violations) => {
+                ui.colored_label(Color32::from_rgb(230, 110, 0), format!("{} rule(s) broken:", violations.len()));
+                egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                    for violation in violations {
+                        ui.label(violation.to_string());
+                    }
+                });
             }
+            None => {}
         }
     }
 
-    let len = tiles.len();
-    assert_eq!(len, (w*h/4) as usize);
+    fn tilemap_ui(&mut self, ui: &mut egui::Ui)
+    {
+        let (name, map) = match self.tilemaps.get(self.selected_tilemap) {
+            Some(entry) => entry,
+            None => {
+                ui.label("No tilemap, import a VRAM dump, save state or Tiled map.");
+                return;
+            }
+        };
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("tilemap")
+                .selected_text(name.as_str())
+                .show_ui(ui, |ui| {
+                    for (i, (name, _)) in self.tilemaps.iter().enumerate() {
+                        ui.selectable_value(&mut self.selected_tilemap, i, name);
+                    }
+                });
+            ui.label(format!("{}x{} tiles", map.width, map.height));
+        });
+
+        // render into a texture only when the tiles, map or palette changed
+        let map = &self.tilemaps[self.selected_tilemap].1;
+        let source = (self.sheet.clone(), self.selected_tilemap, self.palette.clone());
+        if self.tilemap_source.as_ref() != Some(&source) || self.tilemap_texture.is_none() {
+            let rendered = map.render(&self.sheet);
+            let colors = self.colors();
+            let image = egui::ColorImage {
+                size: [rendered.width as usize, rendered.height as usize],
+                pixels: rendered.pixels.iter().map(|i| self.palette[(i % colors) as usize]).collect()
+            };
+            self.tilemap_texture = Some(ui.ctx().load_texture("tilemap", image, egui::TextureOptions::NEAREST));
+            self.tilemap_source = Some(source);
+        }
+
+        if let Some(texture) = &self.tilemap_texture {
+            let pixel_size = 2.0 * std::cmp::max(self.scale as u32, 1) as f32;
+            let response = ui.image(texture, texture.size_vec2() * pixel_size).interact(Sense::hover());
+            if let Some(pos) = response.hover_pos() {
+                let cell = (pos - response.rect.min) / (8.0 * pixel_size);
+                let (x, y) = (cell.x as u32, cell.y as u32);
+                if x < map.width && y < map.height {
+                    let cell = map.get(x, y);
+                    ui.label(format!("({}, {}) tile {} palette {}{}{}{}", x, y, cell.tile, cell.palette,
+                        if cell.flip_x { " flip x" } else { "" },
+                        if cell.flip_y { " flip y" } else { "" },
+                        if cell.priority { " priority" } else { "" }));
+                }
+            }
+        }
+    }
+}
 
-    tiles
+fn lerp_color(a: Color32, b: Color32, t: f32) -> Color32
+{
+    let mix = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * t).round() as u8;
+    Color32::from_rgb(mix(a.r(), b.r()), mix(a.g(), b.g()), mix(a.b(), b.b()))
 }
 
 impl eframe::App for TemplateApp {
+    /// Called when the window is closed or by `Frame::close`, asks first if there are unsaved changes.
+    fn on_close_event(&mut self) -> bool {
+        if self.allowed_to_close || self.unsaved_documents().is_empty() {
+            return true;
+        }
+        self.close_prompt = Some(ClosePrompt::Quit);
+        false
+    }
+
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, eframe::APP_KEY, self);
+        // the saved state holds the canvas, nothing left to recover
+        eframe::set_value(storage, RECOVERY_KEY, &None::<Recovery>);
+        self.autosaved = Some(self.sheet.clone());
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
     /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // no painting while typing into a text field
+        let typing = ctx.memory(|m| m.focus().is_some());
         let input = ctx.input(|i| {
-            if i.key_pressed(egui::Key::Num1) { return 0; }
-            if i.key_pressed(egui::Key::Num2) { return 1; }
-            if i.key_pressed(egui::Key::Num3) { return 2; }
-            if i.key_pressed(egui::Key::Num4) { return 3; }
-            255u8 // invalid
+            match PAINT_KEYS.iter().position(|key| i.key_pressed(*key)) {
+                Some(index) if !typing => index as u8,
+                _ => 255u8 // invalid
+            }
         });
 
-        #[cfg(not(target_arch = "wasm32"))] // no File->Quit on web pages!
+        if self.clean_pending && self.storage.ready() {
+            self.clean_pending = false;
+            self.restore_clean();
+        }
+        self.file_shortcuts(ctx);
+        self.drop_files(ctx);
+        for (action, file) in self.files.take() {
+            self.open_picked(action, file);
+        }
+
+        self.update_ui(ctx, _frame);
+        self.status_ui(ctx);
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             // The top panel is often a good place for a menu bar:
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
-                    if ui.button("Quit").clicked() {
-                        _frame.close();
-                    }
+                    self.file_menu_ui(ui, _frame);
                 });
             });
         });
@@ -283,34 +2278,6 @@ impl eframe::App for TemplateApp {
             });
 
             ui.horizontal(|ui|{
-                if ui.button("Load").clicked() {
-                    if let Some(path) = rfd::FileDialog::new().pick_file() {
-                        self.picked_path = path.display().to_string();
-                        self.load_from_disk(&self.picked_path.clone())
-                    }
-                }
-
-                if ui.button("Save").clicked(){
-                    if self.picked_path.is_empty() {
-                        if let Some(path) = rfd::FileDialog::new().save_file() {
-                            self.picked_path = path.display().to_string();
-                        }
-                    }
-
-                    if !self.picked_path.is_empty() {
-                        self.save_to_disk(&self.picked_path);
-                    }
-                }             
-
-                if ui.button("Save As").clicked(){
-                    if let Some(path) = rfd::FileDialog::new().save_file() {
-                        self.picked_path = path.display().to_string();
-                        if !self.picked_path.is_empty() {
-                            self.save_to_disk(&self.picked_path);
-                        }
-                    }
-                }
-
                 if ui.button("Roundtrip").clicked()
                 {
                     self.save_to_disk("roundtrip.tl");
@@ -318,34 +2285,14 @@ impl eframe::App for TemplateApp {
                 }
 
                 if ui.button("Reset").clicked() {
-                    self.pixels.fill(0);
+                    self.sheet.pixels.fill(0);
                 }
 
                 ui.checkbox(&mut self.instant_save, "InstantSave");
             });
 
             ui.horizontal(|ui|{
-                let mut imported = false;
-                if ui.button("Import").clicked()
-                {
-                    if let Some(path) = rfd::FileDialog::new().pick_file() {
-                        let img = match image::open(path)
-                        {
-                            Ok(file) => file,
-                            Err(err) =>
-                                {
-                                    // TODO log error
-                                    println!("load image: {}", err);
-                                    return;
-                                }
-                        };
-
-                        self.image = img;
-                        imported = true;
-                    }
-                };
-
-                ui.label(format!("w {w} h {h}", w=self.image.width(), h=self.image.height()));
+                ui.label(format!("Image w {w} h {h}", w=self.image.width(), h=self.image.height()));
                 if self.image.width() > 0 {
                     let old_filter = self.filter_type;
                     egui::ComboBox::from_label("FilterMode")
@@ -369,29 +2316,67 @@ impl eframe::App for TemplateApp {
                             ui.selectable_value(&mut self.resize_mode, ResizeMode::ThumbnailExact, "ThumbnailExact");
                         });
 
-                    if old_filter != self.filter_type || old_resize != self.resize_mode || imported {
-                        let thumbnail = match self.resize_mode{
-                            ResizeMode::Default => self.image.resize(self.width, self.height, self.filter_type).to_luma8(),
-                            ResizeMode::Exact => self.image.resize_exact(self.width, self.height, self.filter_type).to_luma8(),
-                            ResizeMode::Fill => self.image.resize_to_fill(self.width, self.height, self.filter_type).to_luma8(),
-                            ResizeMode::Thumbnail => self.image.thumbnail(self.width, self.height).to_luma8(),
-                            ResizeMode::ThumbnailExact => self.image.thumbnail_exact(self.width, self.height).to_luma8(),
-                        };
-
-                        for y in 0..std::cmp::min( self.height, thumbnail.height() ) {
-                            for x in 0..std::cmp::min(self.width, thumbnail.width() ) {
-                                let color = thumbnail.get_pixel(x, y);
-                                let pallet_col = ((color[0] as u32 * self.palette.len() as u32) / 256);
-                                self.set(x, y, pallet_col as u8);
-                            }
-                        }
+                    if old_filter != self.filter_type || old_resize != self.resize_mode {
+                        self.resample_image();
                     }
                 }
             });
 
             ui.horizontal(|ui|{
+                egui::ComboBox::from_label("Format")
+                    .selected_text(self.format.name())
+                    .show_ui(ui, |ui| {
+                        for format in TileFormat::ALL {
+                            ui.selectable_value(&mut self.format, format, format.name());
+                        }
+                    });
+
+            });
+
+            ui.horizontal(|ui|{
+                egui::ComboBox::from_label("Compression")
+                    .selected_text(self.compression.name())
+                    .show_ui(ui, |ui| {
+                        for compression in Compression::ALL {
+                            ui.selectable_value(&mut self.compression, compression, compression.name());
+                        }
+                    });
+            });
+
+            egui::CollapsingHeader::new("Project").show(ui, |ui| {
+                self.project_ui(ui);
+            });
+
+            egui::CollapsingHeader::new("Compressed sizes").show(ui, |ui| {
+                self.update_compressed_sizes();
+                if let Some(computed) = &self.compressed_sizes {
+                    let raw = std::cmp::max(computed.sizes[0].1, 1);
+                    egui::Grid::new("compressed_sizes").striped(true).show(ui, |ui| {
+                        for (compression, size) in &computed.sizes {
+                            ui.selectable_value(&mut self.compression, *compression, compression.name());
+                            ui.label(format!("{} bytes", size));
+                            ui.label(format!("{:.1}%", *size as f64 * 100.0 / raw as f64));
+                            ui.end_row();
+                        }
+                    });
+                }
+            });
+
+            ui.horizontal(|ui|{
+                egui::ComboBox::from_label("Raw VRAM dump tiles")
+                    .selected_text(match self.vram_addressing {
+                        TileAddressing::Unsigned => "$8000",
+                        TileAddressing::Signed => "$8800"
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.vram_addressing, TileAddressing::Unsigned, "$8000");
+                        ui.selectable_value(&mut self.vram_addressing, TileAddressing::Signed, "$8800");
+                    });
+            });
+
+            ui.horizontal_wrapped(|ui|{
                 ui.label("BG palette:");
-                for i in 0..self.palette.len() {
+                for i in 0..self.colors() as usize {
                     let mut newcolor = self.palette[i];
                     ui.label(i.to_string());
                     ui.color_edit_button_srgba(&mut newcolor);            
@@ -400,100 +2385,83 @@ impl eframe::App for TemplateApp {
             });
 
             egui::ComboBox::from_label("TileMode")
-            .selected_text(format!("{:?}", self.mode))
+            .selected_text(format!("{:?}", self.sheet.mode))
             .show_ui(ui, |ui| {
-                ui.selectable_value(&mut self.mode, TileMode::Y8, "8x8");
-                ui.selectable_value(&mut self.mode, TileMode::Y16, "8x16");
+                ui.selectable_value(&mut self.sheet.mode, TileMode::Y8, "8x8");
+                ui.selectable_value(&mut self.sheet.mode, TileMode::Y16, "8x16");
             });
 
-            ui.add(egui::Slider::new(&mut self.scale, 0.0..=4.0).text("Scale"));
+            ui.horizontal(|ui|{
+                ui.add(egui::Slider::new(&mut self.scale, 0.0..=4.0).text("Scale"));
+                ui.checkbox(&mut self.show_animation, "Animation");
+                ui.checkbox(&mut self.show_ripper, "ROM ripper");
+                ui.checkbox(&mut self.show_tilemap, "Tilemap");
+                ui.checkbox(&mut self.show_gbstudio, "GB Studio");
+            });
 
-            let mut width = std::cmp::max(self.width.clone() / 8,1);
-            ui.add(egui::Slider::new(&mut width, 1..=32).text(format!("Width ({w})", w=self.width)));
+            let mut width = std::cmp::max(self.sheet.width.clone() / 8,1);
+            let max_width = std::cmp::max(width, 32);
+            ui.add(egui::Slider::new(&mut width, 1..=max_width).text(format!("Width ({w})", w=self.sheet.width)));
             width *= 8;
 
-            let ystep = self.mode as u32;
+            let ystep = self.sheet.mode as u32;
 
-            let mut height = std::cmp::max(self.height.clone() / ystep, 1);
-            ui.add(egui::Slider::new(&mut height, 1..=32).text(format!("Height ({h})", h=self.height)));
+            let mut height = std::cmp::max(self.sheet.height.clone() / ystep, 1);
+            let max_height = std::cmp::max(height, 32);
+            ui.add(egui::Slider::new(&mut height, 1..=max_height).text(format!("Height ({h})", h=self.sheet.height)));
             height *= ystep;
 
             // rescale
-            if width != self.width || height != self.height
+            if width != self.sheet.width || height != self.sheet.height
             {
-                let mut new_tiles = vec![0; (width*height) as usize];
-                let miny = std::cmp::min(self.height, height);
-                let minx = std::cmp::min(self.width, width);
-
-                for i in 0..miny {
-                    for j in 0..minx {
-                        let dst= (i*width + j) as usize;
-                        let src= (i*(self.width) + j) as usize;
-                        new_tiles[dst] = self.pixels[src];
-                    }
-                }
-
-                self.pixels = new_tiles;
-                self.width = width;
-                self.height = height;
+                self.sheet.resize(width, height);
             }
 
-            // print hex
-            let mut hex_str = String::new();
-            for y in 0..self.height {
-                for x in (0..self.width).step_by(8) {
-                    let mut left: u8 = 0;
-                    let mut right: u8 = 0;
-
-                    for i in 0..8 {
-                        let cur = self.get(x+i, y);
-                        left |= (cur & 0b01) << (7-i);
-                        right |= ( ( cur & 0b10 ) >> 1 ) << (7-i);
-                    }
-
-                    hex_str.push_str(&format!("{:02X} {:02X}", left, right));               
-                    if x+8 < self.width{
-                        hex_str.push(' ');
-                    }
-                }
-                hex_str.push('\n');
-            }
-
-            let hex_edit = egui::TextEdit::multiline(&mut hex_str).code_editor().desired_width(ui.available_width());
-            if ui.add(hex_edit).changed(){
-                let mut y = 0;
-                for row in hex_str.split('\n'){
-                    let mut x_byte = 0;
-                    let mut left: u8 = 0;
-                    let mut right: u8 = 0;
-
-                    for byte in row.split(' ') {
-                        if let Ok(value) = u8::from_str_radix(byte, 16){
-                            if x_byte & 1 == 1 {// odd -> right
-                                right = value;
-                                let x: u32 = x_byte/2;
-                                for i in 0..8 { // x_tile
-                                    let color = ((left >> (7-i)) & 0b1) | ((right >> (7-i)) & 0b1) << 1;
-                                    let pixel = y*self.width + x*8 + i;
-                                    self.pixels[pixel as usize] = color;
+            // print hex, the row bitplane pairs are the GB layout
+            if self.format == TileFormat::Gb {
+                self.update_hex();
+                let hex_edit = egui::TextEdit::multiline(&mut self.hex_str).code_editor().desired_width(ui.available_width());
+                if ui.add(hex_edit).changed(){
+                    let mut y = 0;
+                    for row in self.hex_str.split('\n'){
+                        let mut x_byte = 0;
+                        let mut left: u8 = 0;
+
+                        for byte in row.split(' ') {
+                            if let Ok(value) = u8::from_str_radix(byte, 16){
+                                if x_byte & 1 == 1 {// odd -> right
+                                    let x: u32 = x_byte/2;
+                                    if x < self.sheet.width / 8 && y < self.sheet.height {
+                                        let pixel = y*self.sheet.width + x*8;
+                                        decode_row(left, value, &mut self.sheet.pixels[pixel as usize..]);
+                                    }
+                                } else {
+                                    left = value;
                                 }
-                            } else {
-                                left = value;
                             }
+                            x_byte += 1;
                         }
-                        x_byte += 1;
+                        y += 1;
                     }
-                    y += 1;
                 }
             }
         });
 
         let mut changed = false;
+        let onion = self.onion_frames();
+        let highlight = self.animations.get(self.selected_animation)
+            .and_then(|a| a.frames.get(self.selected_frame))
+            .copied()
+            .filter(|_| self.show_animation);
 
         egui::CentralPanel::default().show(ctx, |ui| {
+            if !self.tabs.is_empty() {
+                self.tabs_ui(ui);
+            }
+
             let cell_size: f32 = 20.0 * self.scale;
             TableBuilder::new(ui)
-            .columns(Column::auto_with_initial_suggestion(cell_size), self.width as usize)
+            .columns(Column::auto_with_initial_suggestion(cell_size), self.sheet.width as usize)
             .striped(false)
             .vertical_scroll_offset(1.0)
             //.resizable(true)
@@ -501,32 +2469,46 @@ impl eframe::App for TemplateApp {
             .max_scroll_height(1600.0)
             .body(|mut body| {
                 body.ui_mut().spacing_mut().item_spacing = Vec2::new(0.0, 0.0);
-                body.rows(cell_size, self.height as usize, |row_index, mut row|{
+                body.rows(cell_size, self.sheet.height as usize, |row_index, mut row|{
                     let r = row_index as u32;
-                    for c in 0..self.width {
+                    for c in 0..self.sheet.width {
                         row.col(|ui|{
-                            let index = (r*self.width+c) as usize;
-                            let i = self.pixels[index] % (self.palette.len() as u8);
-                            let bgcolor = self.palette[i as usize];
+                            let index = (r*self.sheet.width+c) as usize;
+                            let i = self.sheet.pixels[index] % self.colors();
+                            let mut bgcolor = self.palette[i as usize];
                             let mut text = RichText::new( i.to_string() + " " ).background_color(Color32::TRANSPARENT).size(cell_size).monospace();  
+                            let tile = (r / self.sheet.mode as u32) * (self.sheet.width / 8) + c / 8;
+
+                            // blend in the previous animation frame
+                            if let Some((cur, prev)) = onion {
+                                if cur.contains(tile) && tile - cur.first_tile < prev.tile_count {
+                                    if let Some((px, py)) = self.tile_origin(prev.first_tile + tile - cur.first_tile) {
+                                        let ghost = self.get(px + c % 8, py + r % (self.sheet.mode as u32)) % self.colors();
+                                        bgcolor = lerp_color(bgcolor, self.palette[ghost as usize], 0.35);
+                                    }
+                                }
+                            }
 
                             let mut frame = egui::Frame::none();
                             frame = frame.fill(bgcolor);
                             
-                            if r % (self.mode as u32) == 0 || c % 8 == 0{
+                            if r % (self.sheet.mode as u32) == 0 || c % 8 == 0{
                                 text = text.color(Color32::DARK_BLUE);
                             }
+                            if highlight.map_or(false, |f| f.contains(tile)) {
+                                text = text.color(Color32::from_rgb(230, 110, 0));
+                            }
 
                             frame.show(ui, |ui| {
                                 let sense = Sense::click().union(Sense::hover());
                                 let cell = ui.add( Label::new(text).wrap(false).sense(sense) );
-                                let prev = self.pixels[index];
+                                let prev = self.sheet.pixels[index];
                                 if cell.clicked() {
-                                    self.pixels[index] = (i+1) % (self.palette.len() as u8);
-                                } else if cell.hovered() && input != 255u8{
-                                    self.pixels[index] = input % (self.palette.len() as u8);
+                                    self.sheet.pixels[index] = (i+1) % self.colors();
+                                } else if cell.hovered() && input < self.colors() {
+                                    self.sheet.pixels[index] = input;
                                 }
-                                changed |= self.pixels[index] != prev;
+                                changed |= self.sheet.pixels[index] != prev;
                             });
                         });
                     }
@@ -534,8 +2516,47 @@ impl eframe::App for TemplateApp {
             });
         });
 
-        if changed && self.instant_save && !self.picked_path.is_empty() {
-            self.save_to_disk(&self.picked_path);
+        if changed && self.instant_save && !self.picked_path.is_empty() && self.save_to_disk(self.picked_path.clone()) {
+            self.clean = Some(self.sheet.clone());
+        }
+
+        let mut show_animation = self.show_animation;
+        egui::Window::new("Animation").open(&mut show_animation).show(ctx, |ui| {
+            self.animation_ui(ui);
+        });
+        self.show_animation = show_animation;
+
+        let mut show_ripper = self.show_ripper;
+        egui::Window::new("ROM ripper").open(&mut show_ripper).show(ctx, |ui| {
+            self.ripper_ui(ui);
+        });
+        self.show_ripper = show_ripper;
+
+        let mut show_tilemap = self.show_tilemap;
+        egui::Window::new("Tilemap").open(&mut show_tilemap).show(ctx, |ui| {
+            self.tilemap_ui(ui);
+        });
+        self.show_tilemap = show_tilemap;
+
+        let mut show_gbstudio = self.show_gbstudio;
+        egui::Window::new("GB Studio").open(&mut show_gbstudio).show(ctx, |ui| {
+            self.gbstudio_ui(ui);
+        });
+        self.show_gbstudio = show_gbstudio;
+
+        self.recovery_ui(ctx);
+        self.new_sheet_ui(ctx);
+        self.raw_import_ui(ctx);
+        self.browser_ui(ctx);
+        self.drop_zone_ui(ctx);
+        self.close_prompt_ui(ctx, _frame);
+
+        let now = ctx.input(|i| i.time);
+        if let Some(storage) = _frame.storage_mut() {
+            self.autosave(storage, now);
+        }
+        if self.autosaved.as_ref() != Some(&self.sheet) {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(AUTOSAVE_INTERVAL));
         }
 
         if false {
@@ -0,0 +1,405 @@
+use std::fmt;
+use crate::animation::{AnimFrame, Animation, GB_FPS};
+use crate::codec::TileMode;
+use crate::sheet::TileSheet;
+
+const MAGIC: u16 = 0xa5e0;
+const FRAME_MAGIC: u16 = 0xf1fa;
+const HEADER_SIZE: usize = 128;
+const FRAME_HEADER_SIZE: usize = 16;
+/// color depth of indexed sprites, RGBA and grayscale are 32 and 16
+const INDEXED: u16 = 8;
+
+const OLD_PALETTE_CHUNK: u16 = 0x0004;
+const LAYER_CHUNK: u16 = 0x2004;
+const CEL_CHUNK: u16 = 0x2005;
+const TAGS_CHUNK: u16 = 0x2018;
+const PALETTE_CHUNK: u16 = 0x2019;
+
+const LAYER_VISIBLE: u16 = 1;
+const LAYER_BACKGROUND: u16 = 8;
+const LAYER_REFERENCE: u16 = 64;
+const LAYER_IMAGE: u16 = 0;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum AseError
+{
+    Truncated,
+    BadMagic,
+    /// only indexed sprites keep their palette indices
+    ColorDepth(u16),
+    /// a compressed cel did not inflate to its size
+    BadCel { frame: usize, layer: u16 }
+}
+
+impl fmt::Display for AseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AseError::Truncated => write!(f, "Aseprite file truncated"),
+            AseError::BadMagic => write!(f, "not an Aseprite file (bad magic)"),
+            AseError::ColorDepth(bpp) => write!(f, "{} bpp sprite, only indexed sprites are supported", bpp),
+            AseError::BadCel { frame, layer } => write!(f, "corrupt cel in frame {} layer {}", frame, layer)
+        }
+    }
+}
+
+impl std::error::Error for AseError {}
+
+/// Visible layers of one frame flattened to palette indices
+#[derive(Clone, PartialEq, Debug)]
+pub struct AseFrame
+{
+    pub pixels: Vec<u8>,
+    /// milliseconds
+    pub duration: u16
+}
+
+/// Named frame range, tags become animations
+#[derive(Clone, PartialEq, Debug)]
+pub struct AseTag
+{
+    pub name: String,
+    pub from: usize,
+    pub to: usize
+}
+
+/// Flattened frames, palette and tags of an indexed .ase/.aseprite file
+#[derive(Clone, PartialEq, Debug)]
+pub struct Aseprite
+{
+    pub width: u32,
+    pub height: u32,
+    /// index of transparent pixels in non-background layers, fills uncovered pixels
+    pub transparent: u8,
+    pub palette: Vec<[u8; 4]>,
+    pub frames: Vec<AseFrame>,
+    pub tags: Vec<AseTag>
+}
+
+struct Reader<'a>
+{
+    data: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Reader<'a>
+{
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], AseError>
+    {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len).ok_or(AseError::Truncated)?).ok_or(AseError::Truncated)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, AseError>
+    {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, AseError>
+    {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, AseError>
+    {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, AseError>
+    {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn string(&mut self) -> Result<String, AseError>
+    {
+        let len = self.u16()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+}
+
+struct Layer
+{
+    flags: u16,
+    kind: u16,
+    /// visible itself and all of its parent groups
+    visible: bool
+}
+
+#[derive(Clone)]
+struct Cel
+{
+    layer: u16,
+    x: i32,
+    y: i32,
+    z: i32,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>
+}
+
+/// cel chunk data after the layer index, `frames` holds the cels of the previous frames for linked cels
+fn read_cel(r: &mut Reader<'_>, layer: u16, frame: usize, frames: &[Vec<Cel>]) -> Result<Option<Cel>, AseError>
+{
+    let x = r.i16()? as i32;
+    let y = r.i16()? as i32;
+    let _opacity = r.u8()?;
+    let kind = r.u16()?;
+    let z = r.i16()? as i32;
+    r.bytes(5)?;
+
+    match kind {
+        // raw and zlib compressed image
+        0 | 2 => {
+            let width = r.u16()? as u32;
+            let height = r.u16()? as u32;
+            let size = (width * height) as usize;
+            let pixels = if kind == 0 {
+                r.bytes(size)?.to_vec()
+            } else {
+                let rest = r.bytes(r.data.len() - r.pos)?;
+                miniz_oxide::inflate::decompress_to_vec_zlib(rest).map_err(|_| AseError::BadCel { frame, layer })?
+            };
+            if pixels.len() < size {
+                return Err(AseError::BadCel { frame, layer });
+            }
+            Ok(Some(Cel { layer, x, y, z, width, height, pixels }))
+        }
+        // linked to the cel of the same layer in an earlier frame
+        1 => {
+            let linked = r.u16()? as usize;
+            Ok(frames.get(linked).and_then(|cels| cels.iter().find(|c| c.layer == layer)).map(|cel| Cel { x, y, z, ..cel.clone() }))
+        }
+        // tilemap cels are not flattened
+        _ => Ok(None)
+    }
+}
+
+fn read_palette(r: &mut Reader<'_>, palette: &mut Vec<[u8; 4]>) -> Result<(), AseError>
+{
+    let size = r.u32()? as usize;
+    let first = r.u32()? as usize;
+    let last = r.u32()? as usize;
+    r.bytes(8)?;
+    palette.resize(std::cmp::max(palette.len(), std::cmp::min(size, 256)), [0, 0, 0, 0xff]);
+    for i in first..=last {
+        let flags = r.u16()?;
+        let rgba = r.bytes(4)?;
+        if let Some(entry) = palette.get_mut(i) {
+            entry.copy_from_slice(rgba);
+        }
+        if flags & 1 != 0 {
+            r.string()?;
+        }
+    }
+    Ok(())
+}
+
+/// palettes of files written before Aseprite 1.2
+fn read_old_palette(r: &mut Reader<'_>, palette: &mut Vec<[u8; 4]>) -> Result<(), AseError>
+{
+    let packets = r.u16()?;
+    let mut index = 0;
+    for _ in 0..packets {
+        index += r.u8()? as usize;
+        let count = match r.u8()? {
+            0 => 256,
+            n => n as usize
+        };
+        for _ in 0..count {
+            let rgb = r.bytes(3)?;
+            if index >= palette.len() {
+                palette.resize(index + 1, [0, 0, 0, 0xff]);
+            }
+            palette[index] = [rgb[0], rgb[1], rgb[2], 0xff];
+            index += 1;
+        }
+    }
+    Ok(())
+}
+
+fn read_tags(r: &mut Reader<'_>, tags: &mut Vec<AseTag>) -> Result<(), AseError>
+{
+    let count = r.u16()?;
+    r.bytes(8)?;
+    for _ in 0..count {
+        let from = r.u16()? as usize;
+        let to = r.u16()? as usize;
+        // direction, repeat, reserved and the deprecated tag color
+        r.bytes(1 + 2 + 6 + 4)?;
+        tags.push(AseTag { name: r.string()?, from, to });
+    }
+    Ok(())
+}
+
+/// draws the cels of visible image layers bottom to top, transparent pixels only hide on the background layer
+fn flatten(cels: &mut [Cel], layers: &[Layer], width: u32, height: u32, transparent: u8) -> Vec<u8>
+{
+    let mut pixels = vec![transparent; (width * height) as usize];
+    cels.sort_by_key(|cel| (cel.layer as i32 + cel.z, cel.z));
+    for cel in cels.iter() {
+        let layer = match layers.get(cel.layer as usize) {
+            Some(layer) if layer.visible && layer.kind == LAYER_IMAGE => layer,
+            _ => continue
+        };
+        let background = layer.flags & LAYER_BACKGROUND != 0;
+        for cy in 0..cel.height {
+            for cx in 0..cel.width {
+                let (x, y) = (cel.x + cx as i32, cel.y + cy as i32);
+                if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+                    continue;
+                }
+                let index = cel.pixels[(cy * cel.width + cx) as usize];
+                if background || index != transparent {
+                    pixels[(y as u32 * width + x as u32) as usize] = index;
+                }
+            }
+        }
+    }
+    pixels
+}
+
+/// parses an indexed .ase/.aseprite file, flattening the visible layers of every frame
+pub fn read_aseprite(data: &[u8]) -> Result<Aseprite, AseError>
+{
+    let mut r = Reader { data, pos: 0 };
+    let header = r.bytes(HEADER_SIZE)?;
+    let mut h = Reader { data: header, pos: 4 };
+    if h.u16()? != MAGIC {
+        return Err(AseError::BadMagic);
+    }
+    let frame_count = h.u16()? as usize;
+    let width = h.u16()? as u32;
+    let height = h.u16()? as u32;
+    let depth = h.u16()?;
+    if depth != INDEXED {
+        return Err(AseError::ColorDepth(depth));
+    }
+    let transparent = header[28];
+
+    let mut layers: Vec<Layer> = Vec::new();
+    // effective visibility of the groups enclosing the next layer, by child level
+    let mut groups: Vec<bool> = Vec::new();
+    let mut palette = Vec::new();
+    let mut old_palette = Vec::new();
+    let mut tags = Vec::new();
+    let mut cels: Vec<Vec<Cel>> = Vec::with_capacity(frame_count);
+    let mut durations = Vec::with_capacity(frame_count);
+
+    for frame in 0..frame_count {
+        let start = r.pos;
+        let size = r.u32()? as usize;
+        if r.u16()? != FRAME_MAGIC || size < FRAME_HEADER_SIZE {
+            return Err(AseError::BadMagic);
+        }
+        r.u16()?;
+        durations.push(r.u16()?);
+        let end = start.checked_add(size).filter(|end| *end <= data.len()).ok_or(AseError::Truncated)?;
+        r.pos = start + FRAME_HEADER_SIZE;
+
+        let mut frame_cels = Vec::new();
+        while r.pos + 6 <= end {
+            let chunk_start = r.pos;
+            let chunk_size = r.u32()? as usize;
+            let kind = r.u16()?;
+            let chunk_end = chunk_start.checked_add(chunk_size).filter(|e| *e <= end && chunk_size >= 6).ok_or(AseError::Truncated)?;
+            let mut c = Reader { data: &data[..chunk_end], pos: r.pos };
+
+            match kind {
+                LAYER_CHUNK => {
+                    let flags = c.u16()?;
+                    let kind = c.u16()?;
+                    let level = c.u16()? as usize;
+                    groups.truncate(level);
+                    let parent = groups.last().copied().unwrap_or(true);
+                    let visible = parent && flags & LAYER_VISIBLE != 0 && flags & LAYER_REFERENCE == 0;
+                    groups.push(visible);
+                    layers.push(Layer { flags, kind, visible });
+                }
+                CEL_CHUNK => {
+                    let layer = c.u16()?;
+                    if let Some(cel) = read_cel(&mut c, layer, frame, &cels)? {
+                        frame_cels.push(cel);
+                    }
+                }
+                PALETTE_CHUNK => read_palette(&mut c, &mut palette)?,
+                OLD_PALETTE_CHUNK => read_old_palette(&mut c, &mut old_palette)?,
+                TAGS_CHUNK => read_tags(&mut c, &mut tags)?,
+                _ => {}
+            }
+            r.pos = chunk_end;
+        }
+        cels.push(frame_cels);
+        r.pos = end;
+    }
+
+    let frames = cels.iter().zip(durations).map(|(frame_cels, duration)| AseFrame {
+        pixels: flatten(&mut frame_cels.clone(), &layers, width, height, transparent),
+        duration
+    }).collect();
+
+    Ok(Aseprite {
+        width,
+        height,
+        transparent,
+        palette: if palette.is_empty() { old_palette } else { palette },
+        frames,
+        tags
+    })
+}
+
+impl Aseprite
+{
+    /// tiles per row and tile rows of one frame padded to whole tiles
+    fn frame_tiles(&self, mode: TileMode) -> (u32, u32)
+    {
+        let ystep = mode as u32;
+        ((self.width + 7) / 8, (self.height + ystep - 1) / ystep)
+    }
+
+    /// frames stacked top to bottom, each padded to whole tiles with the transparent index
+    pub fn to_sheet(&self, mode: TileMode) -> TileSheet
+    {
+        let (tiles_x, tiles_y) = self.frame_tiles(mode);
+        let frame_height = tiles_y * mode as u32;
+        let mut sheet = TileSheet::new(tiles_x * 8, std::cmp::max(frame_height * self.frames.len() as u32, mode as u32), mode);
+        sheet.pixels.fill(self.transparent);
+        for (f, frame) in self.frames.iter().enumerate() {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    sheet.set(x, f as u32 * frame_height + y, frame.pixels[(y * self.width + x) as usize]);
+                }
+            }
+        }
+        sheet
+    }
+
+    /// frame `f` of `to_sheet`
+    pub fn anim_frame(&self, f: usize, mode: TileMode) -> AnimFrame
+    {
+        let (tiles_x, tiles_y) = self.frame_tiles(mode);
+        let ticks = (self.frames[f].duration as f64 * GB_FPS / 1000.0).round();
+        AnimFrame {
+            first_tile: f as u32 * tiles_x * tiles_y,
+            tile_count: tiles_x * tiles_y,
+            columns: tiles_x,
+            duration: ticks.clamp(1.0, u16::MAX as f64) as u16
+        }
+    }
+
+    /// one animation per tag over the frames of `to_sheet`, or `name` with all frames if there are no tags
+    pub fn animations(&self, name: &str, mode: TileMode) -> Vec<Animation>
+    {
+        let whole = AseTag { name: name.to_owned(), from: 0, to: self.frames.len().saturating_sub(1) };
+        let tags = if self.tags.is_empty() { std::slice::from_ref(&whole) } else { &self.tags[..] };
+        tags.iter()
+            .filter(|tag| tag.from <= tag.to && tag.to < self.frames.len())
+            .map(|tag| Animation {
+                name: tag.name.clone(),
+                frames: (tag.from..=tag.to).map(|f| self.anim_frame(f, mode)).collect()
+            })
+            .collect()
+    }
+}
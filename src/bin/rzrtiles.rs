@@ -0,0 +1,292 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+//! Headless batch conversion, `rzrtiles help` lists the commands
+
+use std::process::ExitCode;
+use rzrtiles::codec::TileMode;
+use rzrtiles::compress::Compression;
+use rzrtiles::convert::{self, Asset, ExportFormat, ExportOptions};
+use rzrtiles::dedupe::dedupe_first;
+use rzrtiles::gbstudio::{self, AssetKind};
+use rzrtiles::project::Project;
+use rzrtiles::storage::FileStorage;
+use rzrtiles::tl::TlHeader;
+
+const USAGE: &str = "usage: rzrtiles <command> [options]
+
+commands:
+  convert <input> <output>     convert between .png (or any image), .tl, .2bpp and NES .chr,
+                               indexed .aseprite files are read with their frames stacked,
+                               .gb writes a bootable test ROM showing the tiles
+                               .tl output keeps the tile format of .tl input
+  info <input.tl>              print the header and tile count of a .tl file
+  dedupe <input> <output>      remove duplicate tiles, --map <file> writes the tile map
+  render <input> <output.png>  render tiles to an image using the DMG shades
+  build <project.json>         write every export target of a project
+  gbstudio <input> <output.png>
+                               write a GB Studio background, or sprite sheet with --sprite,
+                               fails listing the offending tiles if the asset breaks its rules
+
+options:
+  --mode 8|16   tile mode for image, .aseprite, .2bpp and .chr input (default 8)
+  --width <n>   tiles per row for .2bpp and .chr input (default 16)
+  --map <file>  dedupe: write one byte per source tile with its unique tile index
+  --compress rle|pb16|lz77
+                compress .2bpp and .chr output
+  --sprite      gbstudio: validate and color as a sprite sheet
+  --json        print machine-readable JSON to stdout
+
+exit codes: 0 success, 1 conversion error, 2 usage error";
+
+enum Failure
+{
+    Usage(String),
+    Error(String)
+}
+
+struct Options
+{
+    command: String,
+    args: Vec<String>,
+    mode: TileMode,
+    width: u32,
+    map: Option<String>,
+    compression: Compression,
+    sprite: bool,
+    json: bool
+}
+
+fn parse_args(args: Vec<String>) -> Result<Options, Failure>
+{
+    let mut opts = Options {
+        command: String::new(),
+        args: Vec::new(),
+        mode: TileMode::Y8,
+        width: 16,
+        map: None,
+        compression: Compression::None,
+        sprite: false,
+        json: false
+    };
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or_else(|| Failure::Usage(format!("{} needs a value", name)));
+        match arg.as_str() {
+            "--json" => opts.json = true,
+            "--sprite" => opts.sprite = true,
+            "--mode" => {
+                let v = value("--mode")?;
+                opts.mode = v.parse().ok().and_then(TileMode::from_u8)
+                    .ok_or_else(|| Failure::Usage(format!("invalid mode {}, expected 8 or 16", v)))?;
+            }
+            "--width" => {
+                let v = value("--width")?;
+                opts.width = v.parse().ok().filter(|w| *w > 0)
+                    .ok_or_else(|| Failure::Usage(format!("invalid width {}", v)))?;
+            }
+            "--map" => opts.map = Some(value("--map")?),
+            "--compress" => {
+                let v = value("--compress")?;
+                opts.compression = Compression::from_name(&v)
+                    .ok_or_else(|| Failure::Usage(format!("invalid compression {}, expected rle, pb16 or lz77", v)))?;
+            }
+            _ if arg.starts_with("--") => return Err(Failure::Usage(format!("unknown option {}", arg))),
+            _ if opts.command.is_empty() => opts.command = arg,
+            _ => opts.args.push(arg)
+        }
+    }
+
+    Ok(opts)
+}
+
+impl Options
+{
+    fn export_options(&self, format: ExportFormat) -> ExportOptions
+    {
+        ExportOptions { format, mode: self.mode, tiles_per_row: self.width, dedupe: false, compression: self.compression }
+    }
+}
+
+fn load(path: &str, opts: &Options) -> Result<Asset, Failure>
+{
+    convert::load(path, &opts.export_options(ExportFormat::Raw)).map_err(|e| Failure::Error(format!("{}: {}", path, e)))
+}
+
+/// writes the asset in the format implied by the extension, .tl files keep its tile format.
+/// Returns the number of bytes written
+fn save(asset: &Asset, path: &str, opts: &Options) -> Result<usize, Failure>
+{
+    let fail = |err: &dyn std::fmt::Display| Failure::Error(format!("{}: {}", path, err));
+    let format = ExportFormat::from_path(path).ok_or_else(|| Failure::Usage(format!("{}: unknown output format", path)))?;
+    let data = asset.encode(format).map_err(|e| fail(&e))?;
+    let data = convert::compress(data, &opts.export_options(format)).map_err(|e| fail(&e))?;
+    std::fs::write(path, &data).map_err(|e| fail(&e))?;
+    Ok(data.len())
+}
+
+fn positional(opts: &Options, count: usize) -> Result<&[String], Failure>
+{
+    if opts.args.len() != count {
+        return Err(Failure::Usage(format!("{} expects {} argument(s)", opts.command, count)));
+    }
+    Ok(&opts.args)
+}
+
+fn sheet_json(asset: &Asset) -> serde_json::Value
+{
+    serde_json::json!({
+        "mode": asset.sheet.mode as u8,
+        "width": asset.sheet.width,
+        "height": asset.sheet.height,
+        "tiles": asset.tiles
+    })
+}
+
+fn run(opts: &Options) -> Result<serde_json::Value, Failure>
+{
+    match opts.command.as_str() {
+        "convert" => {
+            let args = positional(opts, 2)?;
+            let asset = load(&args[0], opts)?;
+            let bytes = save(&asset, &args[1], opts)?;
+            Ok(serde_json::json!({ "input": args[0], "output": args[1], "bytes": bytes, "sheet": sheet_json(&asset) }))
+        }
+        "info" => {
+            let args = positional(opts, 1)?;
+            let data = std::fs::read(&args[0]).map_err(|e| Failure::Error(format!("{}: {}", args[0], e)))?;
+            let header = TlHeader::parse(&data).map_err(|e| Failure::Error(format!("{}: {}", args[0], e)))?;
+            Ok(serde_json::json!({
+                "file": args[0],
+                "size": data.len(),
+                "version": header.version,
+                "format": header.format.name(),
+                "mode": header.mode as u8,
+                "width": header.width as u32 * 8,
+                "height": header.height as u32 * 8,
+                "tiles_x": header.tiles_x(),
+                "tiles_y": header.tiles_y(),
+                "tiles": header.tile_count(),
+                "data_size": header.data_size()
+            }))
+        }
+        "dedupe" => {
+            let args = positional(opts, 2)?;
+            let asset = load(&args[0], opts)?;
+            let (sheet, format) = (&asset.sheet, asset.format);
+            let deduped = dedupe_first(sheet, format, asset.tiles);
+            let unique = deduped.unique_count(format.bytes_per_tile(sheet.mode));
+            let unique_asset = Asset::from_tiles(format, &deduped.tiles, sheet.tiles_x(), sheet.mode);
+            let bytes = save(&unique_asset, &args[1], opts)?;
+
+            if let Some(map) = &opts.map {
+                if unique > 256 {
+                    return Err(Failure::Error(format!("{} unique tiles do not fit a byte map", unique)));
+                }
+                let indices: Vec<u8> = deduped.map.iter().map(|i| *i as u8).collect();
+                std::fs::write(map, indices).map_err(|e| Failure::Error(format!("{}: {}", map, e)))?;
+            }
+
+            Ok(serde_json::json!({
+                "input": args[0],
+                "output": args[1],
+                "map": opts.map,
+                "bytes": bytes,
+                "tiles": asset.tiles,
+                "unique": unique
+            }))
+        }
+        "render" => {
+            let args = positional(opts, 2)?;
+            let asset = load(&args[0], opts)?;
+            let png = convert::encode(&asset.sheet, ExportFormat::Png).map_err(|e| Failure::Error(format!("{}: {}", args[1], e)))?;
+            std::fs::write(&args[1], png).map_err(|e| Failure::Error(format!("{}: {}", args[1], e)))?;
+            Ok(serde_json::json!({ "input": args[0], "output": args[1], "sheet": sheet_json(&asset) }))
+        }
+        "build" => {
+            let args = positional(opts, 1)?;
+            let fail = |e: rzrtiles::Error| Failure::Error(format!("{}: {}", args[0], e));
+            let project = Project::load(&args[0]).map_err(fail)?;
+            let written = project.build(&mut FileStorage::default(), &args[0]).map_err(fail)?;
+            let outputs: serde_json::Map<String, serde_json::Value> = written.iter()
+                .map(|(path, bytes)| (path.clone(), serde_json::json!(bytes)))
+                .collect();
+            Ok(serde_json::json!({ "project": args[0], "outputs": outputs }))
+        }
+        "gbstudio" => {
+            let args = positional(opts, 2)?;
+            let asset = load(&args[0], opts)?;
+            let sheet = &asset.sheet;
+            let kind = if opts.sprite { AssetKind::Sprite } else { AssetKind::Background };
+            let violations = gbstudio::validate(sheet, kind);
+            if !violations.is_empty() {
+                let list: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+                return Err(Failure::Error(format!("{}: not a valid GB Studio {}:\n  {}", args[0], kind.name(), list.join("\n  "))));
+            }
+            let png = gbstudio::export_png(sheet, kind).map_err(|e| Failure::Error(format!("{}: {}", args[1], e)))?;
+            std::fs::write(&args[1], png).map_err(|e| Failure::Error(format!("{}: {}", args[1], e)))?;
+            Ok(serde_json::json!({
+                "input": args[0],
+                "output": args[1],
+                "kind": kind.name(),
+                "unique": gbstudio::unique_tiles(sheet),
+                "sheet": sheet_json(&asset)
+            }))
+        }
+        "" => Err(Failure::Usage("missing command".to_owned())),
+        cmd => Err(Failure::Usage(format!("unknown command {}", cmd)))
+    }
+}
+
+fn print_value(value: &serde_json::Value)
+{
+    if let Some(obj) = value.as_object() {
+        for (key, v) in obj {
+            match v {
+                serde_json::Value::Object(_) => {
+                    println!("{}:", key);
+                    for (k, v) in v.as_object().into_iter().flatten() {
+                        println!("  {}: {}", k, v);
+                    }
+                }
+                serde_json::Value::String(s) => println!("{}: {}", key, s),
+                _ => println!("{}: {}", key, v)
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode
+{
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "help" || a == "--help" || a == "-h") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let json = args.iter().any(|a| a == "--json");
+    let result = parse_args(args).and_then(|opts| run(&opts));
+
+    let (message, code) = match result {
+        Ok(value) => {
+            if json {
+                println!("{}", value);
+            } else {
+                print_value(&value);
+            }
+            return ExitCode::SUCCESS;
+        }
+        Err(Failure::Usage(msg)) => (msg, 2),
+        Err(Failure::Error(msg)) => (msg, 1)
+    };
+
+    if json {
+        println!("{}", serde_json::json!({ "error": message, "code": code }));
+    } else {
+        eprintln!("rzrtiles: {}", message);
+        if code == 2 {
+            eprintln!("{}", USAGE);
+        }
+    }
+    ExitCode::from(code)
+}
@@ -0,0 +1,243 @@
+use core::fmt;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[repr(u8)]
+pub enum TileMode
+{
+    Y8 = 8,
+    Y16 = 16
+}
+
+impl TileMode
+{
+    pub fn from_u8(value: u8) -> Option<TileMode>
+    {
+        match value {
+            8 => Some(TileMode::Y8),
+            16 => Some(TileMode::Y16),
+            _ => None
+        }
+    }
+
+    /// encoded size of a single tile
+    pub fn bytes_per_tile(self) -> usize
+    {
+        self as usize * 2
+    }
+}
+
+/// Tile data, pixel buffers and sheet dimensions that do not fit together
+#[derive(Clone, PartialEq, Debug)]
+pub enum SizeError
+{
+    /// pixel dimensions that are not whole tiles, or a sheet too large to address
+    Dimensions { width: u32, height: u32 },
+    Input { expected: usize, actual: usize },
+    Output { expected: usize, actual: usize }
+}
+
+impl fmt::Display for SizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SizeError::Dimensions { width, height } => write!(f, "invalid sheet dimensions {}x{}", width, height),
+            SizeError::Input { expected, actual } => write!(f, "input holds {} bytes, expected {}", actual, expected),
+            SizeError::Output { expected, actual } => write!(f, "output holds {} bytes, expected {}", actual, expected)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SizeError {}
+
+/// encoded bytes and pixel count of `w` x `h` tiles of `bytes_per_tile` bytes
+pub(crate) fn sheet_sizes(w: u32, h: u32, mode: TileMode, bytes_per_tile: usize) -> Result<(usize, usize), SizeError>
+{
+    let dimensions = || SizeError::Dimensions { width: w, height: h };
+    let count = (w as usize).checked_mul(h as usize).ok_or_else(dimensions)?;
+    let bytes = count.checked_mul(bytes_per_tile).ok_or_else(dimensions)?;
+    let pixels = count.checked_mul(8 * mode as usize).ok_or_else(dimensions)?;
+    Ok((bytes, pixels))
+}
+
+/// compares the (expected, actual) lengths of the input and output buffers
+pub(crate) fn check_buffers(input: (usize, usize), output: (usize, usize)) -> Result<(), SizeError>
+{
+    if input.0 != input.1 {
+        return Err(SizeError::Input { expected: input.0, actual: input.1 });
+    }
+    if output.0 != output.1 {
+        return Err(SizeError::Output { expected: output.0, actual: output.1 });
+    }
+    Ok(())
+}
+
+/// whole tiles in `w` x `h` pixels
+pub(crate) fn tile_dimensions(w: u32, h: u32, mode: TileMode) -> Result<(u32, u32), SizeError>
+{
+    if w % 8 != 0 || h % mode as u32 != 0 {
+        return Err(SizeError::Dimensions { width: w, height: h });
+    }
+    Ok((w / 8, h / mode as u32))
+}
+
+/// byte i holds bit 7-i of the index, spreads a bitplane byte to 8 pixels
+const SPREAD: [u64; 256] = spread_table();
+/// bit 0 of every byte
+const LANES: u64 = 0x0101_0101_0101_0101;
+/// multiplying by this gathers bit 0 of byte i into bit 7-i of the top byte
+const GATHER: u64 = 0x8040_2010_0804_0201;
+
+const fn spread_table() -> [u64; 256]
+{
+    let mut table = [0u64; 256];
+    let mut b = 0;
+    while b < 256 {
+        let mut i = 0;
+        while i < 8 {
+            table[b] |= (((b >> (7-i)) & 1) as u64) << (8*i);
+            i += 1;
+        }
+        b += 1;
+    }
+    table
+}
+
+/// bitplane `plane` of 8 palette indices, bit 7 is the leftmost pixel
+pub fn encode_plane(pixels: &[u8], plane: u32) -> u8
+{
+    let row = u64::from_le_bytes(pixels[..8].try_into().unwrap());
+    (((row >> plane) & LANES).wrapping_mul(GATHER) >> 56) as u8
+}
+
+/// bitplane bytes, lowest plane first, to 8 palette indices
+pub fn decode_planes(planes: &[u8], out: &mut [u8])
+{
+    let row = planes.iter().enumerate().fold(0u64, |row, (p, &b)| row | (SPREAD[b as usize] << p));
+    out[..8].copy_from_slice(&row.to_le_bytes());
+}
+
+/// 8 palette indices to the low and high bitplane byte of a tile row, only bit 0 and 1 of each index are used
+pub fn encode_row(pixels: &[u8]) -> (u8, u8)
+{
+    (encode_plane(pixels, 0), encode_plane(pixels, 1))
+}
+
+/// low and high bitplane byte of a tile row to 8 palette indices
+pub fn decode_row(left: u8, right: u8, out: &mut [u8])
+{
+    decode_planes(&[left, right], out);
+}
+
+/// Bit at a time reference implementation the table driven rows are checked against
+pub mod scalar
+{
+    pub fn encode_row(pixels: &[u8]) -> (u8, u8)
+    {
+        let mut left: u8 = 0;
+        let mut right: u8 = 0;
+
+        for (x, &cur) in pixels[..8].iter().enumerate() {
+            left |= (cur & 0b01) << (7-x);
+            right |= ( ( cur & 0b10 ) >> 1 ) << (7-x);
+        }
+        (left, right)
+    }
+
+    pub fn decode_row(left: u8, right: u8, out: &mut [u8])
+    {
+        for (i, color) in out[..8].iter_mut().enumerate() { // x_tile
+            *color = ((left >> (7-i)) & 0b1) | ((right >> (7-i)) & 0b1) << 1;
+        }
+    }
+}
+
+/// encodes the tile whose top left pixel is `pixels[0]`, rows are `stride` pixels apart.
+/// The tile height is `out.len() / 2` rows
+pub fn encode_tile(pixels: &[u8], stride: usize, out: &mut [u8])
+{
+    for (y, row) in out.chunks_exact_mut(2).enumerate() {
+        let (left, right) = encode_row(&pixels[y*stride..]);
+        row[0] = left;
+        row[1] = right;
+    }
+}
+
+/// decodes one tile of `data.len() / 2` rows into `out`, rows are `stride` pixels apart
+pub fn decode_tile(data: &[u8], out: &mut [u8], stride: usize)
+{
+    for (y, row) in data.chunks_exact(2).enumerate() {
+        decode_row(row[0], row[1], &mut out[y*stride..]);
+    }
+}
+
+/// w and h number of tiles, `out` receives w*8 x h*mode pixels.
+/// Panics if the buffers do not hold w x h tiles, `try_decode_into` returns an error instead
+pub fn decode_into(data: &[u8], w: u32, h: u32, mode: TileMode, out: &mut [u8])
+{
+    if let Err(err) = try_decode_into(data, w, h, mode, out) {
+        panic!("{}", err);
+    }
+}
+
+/// like `decode_into`, failing if `data` is not w x h tiles or `out` not their pixels
+pub fn try_decode_into(data: &[u8], w: u32, h: u32, mode: TileMode, out: &mut [u8]) -> Result<(), SizeError>
+{
+    let ystep = mode as usize;
+    let bytes_per_tile = mode.bytes_per_tile();
+    let width = w as usize * 8;
+    let (bytes, pixels) = sheet_sizes(w, h, mode, bytes_per_tile)?;
+    check_buffers((bytes, data.len()), (pixels, out.len()))?;
+
+    for (t, tile) in data.chunks_exact(bytes_per_tile).enumerate() {
+        let x = t % w as usize;
+        let y = t / w as usize;
+        decode_tile(tile, &mut out[y*ystep*width + x*8..], width);
+    }
+    Ok(())
+}
+
+/// w and h in number of pixels, `out` receives w*h/4 bytes of tiles in export order.
+/// Panics if w and h are not whole tiles or the buffers do not match them, `try_encode_into` returns an error instead
+pub fn encode_into(data: &[u8], w: u32, h: u32, mode: TileMode, out: &mut [u8])
+{
+    if let Err(err) = try_encode_into(data, w, h, mode, out) {
+        panic!("{}", err);
+    }
+}
+
+/// like `encode_into`, failing if w and h are not whole tiles or the buffers do not match them
+pub fn try_encode_into(data: &[u8], w: u32, h: u32, mode: TileMode, out: &mut [u8]) -> Result<(), SizeError>
+{
+    let ystep = mode as usize;
+    let bytes_per_tile = mode.bytes_per_tile();
+    let width = w as usize;
+    let (tiles_x, tiles_y) = tile_dimensions(w, h, mode)?;
+    let (bytes, pixels) = sheet_sizes(tiles_x, tiles_y, mode, bytes_per_tile)?;
+    check_buffers((pixels, data.len()), (bytes, out.len()))?;
+
+    for (t, tile) in out.chunks_exact_mut(bytes_per_tile).enumerate() {
+        let x = t % (width / 8);
+        let y = t / (width / 8);
+        encode_tile(&data[y*ystep*width + x*8..], width, tile);
+    }
+    Ok(())
+}
+
+/// w and h number of tiles
+pub fn gb_tiles_to_pixels(data: &[u8], w: u32, h: u32, mode: TileMode) -> Vec<u8>
+{
+    let mut pixels: Vec<u8> = vec![0; (w*8*h*mode as u32) as usize];
+    decode_into(data, w, h, mode, &mut pixels);
+    pixels
+}
+
+// w and h in number of pixels
+pub fn pixels_to_gb_tiles(data: &[u8], w: u32, h: u32, mode: TileMode) -> Vec<u8>
+{
+    let mut tiles: Vec<u8> = vec![0; (w*h/4) as usize];
+    encode_into(data, w, h, mode, &mut tiles);
+    tiles
+}
@@ -0,0 +1,320 @@
+use core::fmt;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// longest RLE run and literal span
+const RLE_MAX: usize = 128;
+/// LZ77 matches are 3 to 18 bytes long, up to 4096 bytes back
+const LZ_MIN_MATCH: usize = 3;
+const LZ_MAX_MATCH: usize = LZ_MIN_MATCH + 15;
+const LZ_WINDOW: usize = 4096;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum CompressError
+{
+    /// the stream ends inside a packet
+    Truncated,
+    /// an LZ77 match reaches back before the start of the output
+    BadOffset { at: usize, offset: usize }
+}
+
+impl fmt::Display for CompressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressError::Truncated => write!(f, "compressed data truncated"),
+            CompressError::BadOffset { at, offset } => write!(f, "match at output byte {} reaches {} bytes back", at, offset)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CompressError {}
+
+/// Compression applied to exported tile data. None of the formats store the decompressed
+/// size, decompressors stop at the end of the input
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Compression
+{
+    None,
+    /// control byte n < 0x80 is followed by n+1 literals, n >= 0x80 by one byte repeated n-0x7e times
+    Rle,
+    /// PB16 as used by several homebrew engines: a control byte per 8 output bytes, MSB first,
+    /// a set bit repeats the byte two positions back (the same bitplane of the previous row)
+    /// and a clear bit reads a literal
+    Pb16,
+    /// LZSS: a flag byte per 8 items, LSB first, a set bit is a literal byte and a clear bit a
+    /// match of two bytes `LLLLOOOO OOOOOOOO`, length L+3 and distance O+1
+    Lz77
+}
+
+impl Compression
+{
+    pub const ALL: [Compression; 4] = [Compression::None, Compression::Rle, Compression::Pb16, Compression::Lz77];
+
+    pub fn name(self) -> &'static str
+    {
+        match self {
+            Compression::None => "None",
+            Compression::Rle => "RLE",
+            Compression::Pb16 => "PB16",
+            Compression::Lz77 => "LZ77"
+        }
+    }
+
+    /// appended to the file name of compressed exports
+    pub fn extension(self) -> Option<&'static str>
+    {
+        match self {
+            Compression::None => None,
+            Compression::Rle => Some("rle"),
+            Compression::Pb16 => Some("pb16"),
+            Compression::Lz77 => Some("lz")
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Compression>
+    {
+        Compression::ALL.iter().copied().find(|c| c.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn compress(self, data: &[u8]) -> Vec<u8>
+    {
+        match self {
+            Compression::None => data.to_vec(),
+            Compression::Rle => rle_compress(data),
+            Compression::Pb16 => pb16_compress(data),
+            Compression::Lz77 => lz77_compress(data)
+        }
+    }
+
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>, CompressError>
+    {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Rle => rle_decompress(data),
+            Compression::Pb16 => pb16_decompress(data),
+            Compression::Lz77 => lz77_decompress(data)
+        }
+    }
+}
+
+pub fn rle_compress(data: &[u8]) -> Vec<u8>
+{
+    let mut out = Vec::new();
+    let mut literals = 0..0;
+    let mut i = 0;
+
+    let flush = |out: &mut Vec<u8>, literals: &core::ops::Range<usize>| {
+        for chunk in data[literals.clone()].chunks(RLE_MAX) {
+            out.push(chunk.len() as u8 - 1);
+            out.extend_from_slice(chunk);
+        }
+    };
+
+    while i < data.len() {
+        let run = data[i..].iter().take(RLE_MAX + 1).take_while(|b| **b == data[i]).count();
+        if run >= 2 {
+            flush(&mut out, &literals);
+            out.push((run + 0x7e) as u8);
+            out.push(data[i]);
+            i += run;
+            literals = i..i;
+        } else {
+            i += 1;
+            literals.end = i;
+        }
+    }
+    flush(&mut out, &literals);
+    out
+}
+
+pub fn rle_decompress(data: &[u8]) -> Result<Vec<u8>, CompressError>
+{
+    let mut out = Vec::new();
+    let mut i = 0;
+    while let Some(&control) = data.get(i) {
+        let control = control as usize;
+        if control < 0x80 {
+            let literals = data.get(i + 1..i + 2 + control).ok_or(CompressError::Truncated)?;
+            out.extend_from_slice(literals);
+            i += 2 + control;
+        } else {
+            let byte = *data.get(i + 1).ok_or(CompressError::Truncated)?;
+            out.extend(core::iter::repeat(byte).take(control - 0x7e));
+            i += 2;
+        }
+    }
+    Ok(out)
+}
+
+pub fn pb16_compress(data: &[u8]) -> Vec<u8>
+{
+    let mut out = Vec::new();
+    let mut prev = [0u8; 2];
+    for packet in data.chunks(8) {
+        let control_at = out.len();
+        out.push(0);
+        for (i, &byte) in packet.iter().enumerate() {
+            if byte == prev[1] {
+                out[control_at] |= 0x80 >> i;
+            } else {
+                out.push(byte);
+            }
+            prev = [byte, prev[0]];
+        }
+    }
+    out
+}
+
+pub fn pb16_decompress(data: &[u8]) -> Result<Vec<u8>, CompressError>
+{
+    let mut out = Vec::new();
+    let mut prev = [0u8; 2];
+    let mut i = 0;
+    while let Some(&control) = data.get(i) {
+        i += 1;
+        for bit in 0..8 {
+            let byte = if control & (0x80 >> bit) != 0 {
+                prev[1]
+            } else {
+                match data.get(i) {
+                    Some(&byte) => {
+                        i += 1;
+                        byte
+                    }
+                    // a short last packet pads with literal bits
+                    None if bit > 0 => break,
+                    None => return Err(CompressError::Truncated)
+                }
+            };
+            out.push(byte);
+            prev = [byte, prev[0]];
+        }
+    }
+    Ok(out)
+}
+
+/// bits of the hash of the first `LZ_MIN_MATCH` bytes of a match
+const LZ_HASH_BITS: u32 = 12;
+
+/// Earlier positions of `data` chained by the hash of the `LZ_MIN_MATCH` bytes starting there,
+/// so the match search only visits positions that can start a match
+struct Lz77Chains<'a>
+{
+    data: &'a [u8],
+    /// latest position per hash
+    head: Vec<usize>,
+    /// the position before each one with the same hash
+    prev: Vec<usize>,
+    /// positions before this are in the chains
+    added: usize
+}
+
+impl<'a> Lz77Chains<'a>
+{
+    const NONE: usize = usize::MAX;
+
+    fn new(data: &'a [u8]) -> Lz77Chains<'a>
+    {
+        Lz77Chains { data, head: vec![Self::NONE; 1 << LZ_HASH_BITS], prev: vec![Self::NONE; data.len()], added: 0 }
+    }
+
+    fn hash(&self, i: usize) -> usize
+    {
+        let key = (self.data[i] as u32) << 16 | (self.data[i + 1] as u32) << 8 | self.data[i + 2] as u32;
+        (key.wrapping_mul(2654435761) >> (32 - LZ_HASH_BITS)) as usize
+    }
+
+    /// longest match for `data[i..]` in the window before it, as (length, distance).
+    /// Positions are added up to `i`, so calls must not go backwards
+    fn find(&mut self, i: usize) -> (usize, usize)
+    {
+        let data = self.data;
+        let max = core::cmp::min(LZ_MAX_MATCH, data.len() - i);
+        if max < LZ_MIN_MATCH {
+            return (0, 0);
+        }
+        while self.added < i {
+            let hash = self.hash(self.added);
+            self.prev[self.added] = self.head[hash];
+            self.head[hash] = self.added;
+            self.added += 1;
+        }
+
+        let mut best = (0, 0);
+        let mut start = self.head[self.hash(i)];
+        while start != Self::NONE && i - start <= LZ_WINDOW {
+            // matches may overlap the bytes they produce
+            let len = (0..max).take_while(|k| data[start + k] == data[i + k]).count();
+            if len > best.0 {
+                best = (len, i - start);
+                if len == max {
+                    break;
+                }
+            }
+            start = self.prev[start];
+        }
+        best
+    }
+}
+
+pub fn lz77_compress(data: &[u8]) -> Vec<u8>
+{
+    let mut out = Vec::new();
+    let mut flags_at = 0;
+    let mut item = 8;
+    let mut chains = Lz77Chains::new(data);
+    let mut i = 0;
+    while i < data.len() {
+        if item == 8 {
+            flags_at = out.len();
+            out.push(0);
+            item = 0;
+        }
+
+        let (len, distance) = chains.find(i);
+        if len >= LZ_MIN_MATCH {
+            let code = ((len - LZ_MIN_MATCH) << 12) | (distance - 1);
+            out.push((code >> 8) as u8);
+            out.push(code as u8);
+            i += len;
+        } else {
+            out[flags_at] |= 1 << item;
+            out.push(data[i]);
+            i += 1;
+        }
+        item += 1;
+    }
+    out
+}
+
+pub fn lz77_decompress(data: &[u8]) -> Result<Vec<u8>, CompressError>
+{
+    let mut out: Vec<u8> = Vec::new();
+    let mut i = 0;
+    while let Some(&flags) = data.get(i) {
+        i += 1;
+        for item in 0..8 {
+            if i == data.len() {
+                break;
+            }
+            if flags & (1 << item) != 0 {
+                out.push(data[i]);
+                i += 1;
+            } else {
+                let code = data.get(i..i + 2).ok_or(CompressError::Truncated)?;
+                let code = (code[0] as usize) << 8 | code[1] as usize;
+                let (len, distance) = ((code >> 12) + LZ_MIN_MATCH, (code & 0xfff) + 1);
+                if distance > out.len() {
+                    return Err(CompressError::BadOffset { at: out.len(), offset: distance });
+                }
+                for _ in 0..len {
+                    out.push(out[out.len() - distance]);
+                }
+                i += 2;
+            }
+        }
+    }
+    Ok(out)
+}
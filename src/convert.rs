@@ -0,0 +1,247 @@
+use std::io::Cursor;
+use std::path::Path;
+use crate::aseprite;
+use crate::codec::TileMode;
+use crate::compress::Compression;
+use crate::dedupe::dedupe_first;
+use crate::error::Error;
+use crate::format::{TileFormat, pad_to_chr_banks};
+use crate::rom;
+use crate::sheet::{TileSheet, GB_SHADES};
+use crate::tl;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum InputFormat
+{
+    /// rzrTiles .tl file
+    Tl,
+    /// headerless 2bpp tile data
+    Raw,
+    /// NES CHR ROM data
+    Chr,
+    /// indexed Aseprite sprite, frames are stacked top to bottom
+    Aseprite,
+    /// any image format supported by the image crate, quantized by luminance
+    Image
+}
+
+impl InputFormat
+{
+    pub fn from_path(path: impl AsRef<Path>) -> InputFormat
+    {
+        match extension(path.as_ref()).as_str() {
+            "tl" => InputFormat::Tl,
+            "2bpp" | "bin" => InputFormat::Raw,
+            "chr" => InputFormat::Chr,
+            "ase" | "aseprite" => InputFormat::Aseprite,
+            _ => InputFormat::Image
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ExportFormat
+{
+    Tl,
+    Raw,
+    /// NES CHR padded to whole 8KB banks
+    Chr,
+    /// PNG rendered with the DMG shades, sheets using more than 4 colors fail with `Error::Unsupported`
+    Png,
+    /// bootable Game Boy ROM showing the sheet
+    Rom
+}
+
+impl ExportFormat
+{
+    pub fn from_path(path: impl AsRef<Path>) -> Option<ExportFormat>
+    {
+        match extension(path.as_ref()).as_str() {
+            "tl" => Some(ExportFormat::Tl),
+            "2bpp" | "bin" => Some(ExportFormat::Raw),
+            "chr" => Some(ExportFormat::Chr),
+            "png" => Some(ExportFormat::Png),
+            "gb" => Some(ExportFormat::Rom),
+            _ => None
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ExportOptions
+{
+    pub format: ExportFormat,
+    /// tile mode for image and raw input, .tl files carry their own
+    pub mode: TileMode,
+    /// tiles per row for raw and CHR input and deduplicated output
+    pub tiles_per_row: u32,
+    /// drop exact duplicate tiles before exporting
+    pub dedupe: bool,
+    /// applied to raw and CHR output
+    pub compression: Compression
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Raw,
+            mode: TileMode::Y8,
+            tiles_per_row: 16,
+            dedupe: false,
+            compression: Compression::None
+        }
+    }
+}
+
+/// A decoded sheet, the tile format it was stored in and the number of tiles in the source.
+/// Raw and CHR input is padded to whole rows, so the sheet can hold more tiles than `tiles`
+#[derive(Clone, PartialEq, Debug)]
+pub struct Asset
+{
+    pub sheet: TileSheet,
+    pub format: TileFormat,
+    pub tiles: u32
+}
+
+impl Asset
+{
+    /// an asset using every tile of the sheet
+    pub fn new(sheet: TileSheet, format: TileFormat) -> Self
+    {
+        let tiles = sheet.tile_count();
+        Self { sheet, format, tiles }
+    }
+
+    /// `data` laid out `tiles_x` tiles per row, counting only the tiles actually in `data`
+    pub fn from_tiles(format: TileFormat, data: &[u8], tiles_x: u32, mode: TileMode) -> Self
+    {
+        let tiles = (data.len() / format.bytes_per_tile(mode)) as u32;
+        Self { sheet: TileSheet::from_tiles_as(format, data, tiles_x, mode), format, tiles }
+    }
+
+    /// encodes the asset as `format`, see `encode_as`. Raw and CHR output stop after the source tiles
+    pub fn encode(&self, format: ExportFormat) -> Result<Vec<u8>, Error>
+    {
+        let mut data = encode_as(&self.sheet, self.format, format)?;
+        match format {
+            ExportFormat::Raw => data.truncate(self.tiles as usize * TileFormat::Gb.bytes_per_tile(self.sheet.mode)),
+            ExportFormat::Chr => {
+                data.truncate(self.tiles as usize * TileFormat::Nes.bytes_per_tile(self.sheet.mode));
+                pad_to_chr_banks(&mut data);
+            }
+            _ => ()
+        }
+        Ok(data)
+    }
+}
+
+fn extension(path: &Path) -> String
+{
+    path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase()
+}
+
+/// decodes `data` into an asset, .tl files carry their tile format and everything else is 2bpp.
+/// `options.mode` applies to everything but .tl and `options.tiles_per_row` to raw input
+pub fn load_bytes(data: &[u8], format: InputFormat, options: &ExportOptions) -> Result<Asset, Error>
+{
+    match format {
+        InputFormat::Tl => {
+            let (sheet, format) = tl::read_tl(data)?;
+            Ok(Asset::new(sheet, format))
+        }
+        InputFormat::Raw => Ok(Asset::from_tiles(TileFormat::Gb, data, options.tiles_per_row, options.mode)),
+        InputFormat::Chr => Ok(Asset::from_tiles(TileFormat::Nes, data, options.tiles_per_row, options.mode)),
+        InputFormat::Aseprite => Ok(Asset::new(aseprite::read_aseprite(data)?.to_sheet(options.mode), TileFormat::Gb)),
+        InputFormat::Image => {
+            let img = image::load_from_memory(data)?;
+            Ok(Asset::new(TileSheet::from_luma(&img.to_luma8(), options.mode), TileFormat::Gb))
+        }
+    }
+}
+
+/// reads and decodes a file, the format is derived from the extension
+pub fn load(path: impl AsRef<Path>, options: &ExportOptions) -> Result<Asset, Error>
+{
+    let path = path.as_ref();
+    let data = std::fs::read(path)?;
+    load_bytes(&data, InputFormat::from_path(path), options)
+}
+
+/// fails if the sheet uses more colors than `format` can store, instead of dropping bitplanes
+fn check_colors(sheet: &TileSheet, format: TileFormat) -> Result<(), Error>
+{
+    match sheet.pixels.iter().copied().max() {
+        Some(index) if index as usize >= format.colors() => Err(Error::Unsupported(format!("color index {} in {} tiles", index, format.name()))),
+        _ => Ok(())
+    }
+}
+
+/// encodes the sheet as `format`, .tl files with 2bpp GB tiles.
+/// Fails with `Error::Unsupported` if the sheet uses more colors than the output stores, e.g. a 4bpp sheet as PNG
+pub fn encode(sheet: &TileSheet, format: ExportFormat) -> Result<Vec<u8>, Error>
+{
+    encode_as(sheet, TileFormat::Gb, format)
+}
+
+/// encodes the sheet as `format`, .tl files keep `tile_format` while the other outputs are 2bpp.
+/// Fails like `encode` if a color index does not fit the output
+pub fn encode_as(sheet: &TileSheet, tile_format: TileFormat, format: ExportFormat) -> Result<Vec<u8>, Error>
+{
+    check_colors(sheet, match format {
+        ExportFormat::Tl => tile_format,
+        ExportFormat::Chr => TileFormat::Nes,
+        _ => TileFormat::Gb
+    })?;
+    match format {
+        ExportFormat::Tl => Ok(tl::to_tl_as(sheet, tile_format)?),
+        ExportFormat::Raw => Ok(sheet.export()),
+        ExportFormat::Chr => {
+            let mut chr = sheet.export_as(TileFormat::Nes);
+            pad_to_chr_banks(&mut chr);
+            Ok(chr)
+        }
+        ExportFormat::Png => encode_png(sheet, &GB_SHADES),
+        ExportFormat::Rom => Ok(rom::sheet_rom(sheet, rom::DEFAULT_BGP)?)
+    }
+}
+
+/// PNG of the sheet with palette index i drawn as `palette[i]`
+pub fn encode_png(sheet: &TileSheet, palette: &[[u8; 4]]) -> Result<Vec<u8>, Error>
+{
+    let mut png = Cursor::new(Vec::new());
+    sheet.to_rgba(palette).write_to(&mut png, image::ImageOutputFormat::Png)?;
+    Ok(png.into_inner())
+}
+
+/// applies `options.compression` to tile data encoded as `options.format`,
+/// the other formats have headers that would be compressed along
+pub fn compress(data: Vec<u8>, options: &ExportOptions) -> Result<Vec<u8>, Error>
+{
+    match (options.compression, options.format) {
+        (Compression::None, _) => Ok(data),
+        (compression, ExportFormat::Raw | ExportFormat::Chr) => Ok(compression.compress(&data)),
+        (compression, format) => Err(Error::Unsupported(format!("{} compression of {:?} output", compression.name(), format)))
+    }
+}
+
+/// the asset as it will be exported, with duplicate tiles in its format removed if requested
+pub fn prepare(asset: Asset, options: &ExportOptions) -> Asset
+{
+    if !options.dedupe {
+        return asset;
+    }
+    let deduped = dedupe_first(&asset.sheet, asset.format, asset.tiles);
+    Asset::from_tiles(asset.format, &deduped.tiles, options.tiles_per_row, asset.sheet.mode)
+}
+
+/// converts an asset file in one go, intended for build scripts:
+/// ```no_run
+/// let options = rzrtiles::ExportOptions { format: rzrtiles::ExportFormat::Raw, ..Default::default() };
+/// let tiles = rzrtiles::convert("art/font.png", options).unwrap();
+/// ```
+pub fn convert(input: impl AsRef<Path>, options: ExportOptions) -> Result<Vec<u8>, Error>
+{
+    let data = prepare(load(input, &options)?, &options).encode(options.format)?;
+    compress(data, &options)
+}
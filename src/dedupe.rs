@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use crate::format::TileFormat;
+use crate::sheet::TileSheet;
+
+/// Unique tiles of a sheet and the index of the unique tile for every source tile
+pub struct Deduped
+{
+    /// unique tiles in order of first occurrence, encoded in the format they were compared in
+    pub tiles: Vec<u8>,
+    pub map: Vec<u32>
+}
+
+impl Deduped
+{
+    pub fn unique_count(&self, bytes_per_tile: usize) -> usize
+    {
+        self.tiles.len() / bytes_per_tile
+    }
+}
+
+/// removes exact duplicate 2bpp tiles, the map is in export order of the source sheet
+pub fn dedupe(sheet: &TileSheet) -> Deduped
+{
+    dedupe_as(sheet, TileFormat::Gb)
+}
+
+/// like `dedupe` comparing the tiles encoded as `format`
+pub fn dedupe_as(sheet: &TileSheet, format: TileFormat) -> Deduped
+{
+    dedupe_first(sheet, format, sheet.tile_count())
+}
+
+/// like `dedupe_as` for the first `count` tiles only, leaving out the padding of a partial last row
+pub fn dedupe_first(sheet: &TileSheet, format: TileFormat, count: u32) -> Deduped
+{
+    let bytes_per_tile = format.bytes_per_tile(sheet.mode);
+    let data = sheet.export_as(format);
+
+    let mut lookup: HashMap<&[u8], u32> = HashMap::new();
+    let mut tiles = Vec::new();
+    let mut map = Vec::with_capacity(count.min(sheet.tile_count()) as usize);
+
+    for tile in data.chunks_exact(bytes_per_tile).take(count as usize) {
+        let next = lookup.len() as u32;
+        let index = *lookup.entry(tile).or_insert_with(|| {
+            tiles.extend_from_slice(tile);
+            next
+        });
+        map.push(index);
+    }
+
+    Deduped { tiles, map }
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+use crate::aseprite::AseError;
+use crate::gbstudio::Violations;
+use crate::rom::RomError;
+use crate::tiled::TiledError;
+use crate::tl::TlError;
+
+/// Errors of the conversion API
+#[derive(Debug)]
+pub enum Error
+{
+    Io(std::io::Error),
+    Tl(TlError),
+    Rom(RomError),
+    Tiled(TiledError),
+    Image(image::ImageError),
+    Json(serde_json::Error),
+    Aseprite(AseError),
+    GbStudio(Violations),
+    Unsupported(String)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Tl(err) => write!(f, "{}", err),
+            Error::Rom(err) => write!(f, "{}", err),
+            Error::Tiled(err) => write!(f, "{}", err),
+            Error::Image(err) => write!(f, "{}", err),
+            Error::Json(err) => write!(f, "{}", err),
+            Error::Aseprite(err) => write!(f, "{}", err),
+            Error::GbStudio(err) => write!(f, "{}", err),
+            Error::Unsupported(what) => write!(f, "unsupported: {}", what)
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Tl(err) => Some(err),
+            Error::Rom(err) => Some(err),
+            Error::Tiled(err) => Some(err),
+            Error::Image(err) => Some(err),
+            Error::Json(err) => Some(err),
+            Error::Aseprite(err) => Some(err),
+            Error::GbStudio(err) => Some(err),
+            Error::Unsupported(_) => None
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<TlError> for Error {
+    fn from(err: TlError) -> Self {
+        Error::Tl(err)
+    }
+}
+
+impl From<RomError> for Error {
+    fn from(err: RomError) -> Self {
+        Error::Rom(err)
+    }
+}
+
+impl From<TiledError> for Error {
+    fn from(err: TiledError) -> Self {
+        Error::Tiled(err)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(err: image::ImageError) -> Self {
+        Error::Image(err)
+    }
+}
+
+impl From<AseError> for Error {
+    fn from(err: AseError) -> Self {
+        Error::Aseprite(err)
+    }
+}
+
+impl From<Violations> for Error {
+    fn from(err: Violations) -> Self {
+        Error::GbStudio(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
@@ -0,0 +1,207 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use crate::status::Status;
+
+/// What a picked file is used for
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FileAction
+{
+    /// .tl document opened in a new tab
+    Open,
+    Image,
+    /// tiles in the selected format
+    Raw,
+    Vram,
+    Aseprite,
+    /// binary for the ROM ripper
+    Rom,
+    Project,
+    /// .tlproj project archive
+    Archive
+}
+
+impl FileAction
+{
+    /// what dropping a file named `name` does, by its extension
+    pub fn for_file(name: &str) -> Option<FileAction>
+    {
+        let extension = name.rsplit_once('.')?.1.to_ascii_lowercase();
+        match extension.as_str() {
+            "tl" => Some(FileAction::Open),
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" => Some(FileAction::Image),
+            "2bpp" | "chr" | "4bpp" | "bin" => Some(FileAction::Raw),
+            "aseprite" | "ase" => Some(FileAction::Aseprite),
+            "tlproj" => Some(FileAction::Archive),
+            _ => None
+        }
+    }
+
+    pub fn name(self) -> &'static str
+    {
+        match self {
+            FileAction::Open => "open",
+            FileAction::Image => "import image",
+            FileAction::Raw => "import raw tiles",
+            FileAction::Vram => "import VRAM",
+            FileAction::Aseprite => "import Aseprite",
+            FileAction::Rom => "rip tiles",
+            FileAction::Project => "open project",
+            FileAction::Archive => "import project archive"
+        }
+    }
+}
+
+/// Contents of a picked or dropped file, web builds only get the name and no path
+pub struct PickedFile
+{
+    pub name: String,
+    pub path: Option<PathBuf>,
+    pub data: Vec<u8>
+}
+
+/// Reads and writes files for the editor. Native builds use blocking dialogs and the file system,
+/// the web build picks files with the browser's file input and saves them as downloads.
+/// Picked files arrive through `take`, on the web a few frames after `pick` returned.
+/// Failures are reported to `status`
+#[derive(Default)]
+pub struct Files
+{
+    picked: Rc<RefCell<Vec<(FileAction, PickedFile)>>>,
+    status: Status
+}
+
+impl Files
+{
+    pub fn new(status: Status) -> Files
+    {
+        Files { picked: Rc::default(), status }
+    }
+
+    /// asks for a file to read, an empty `extensions` accepts any file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pick(&self, _ctx: &egui::Context, action: FileAction, filter: &str, extensions: &[&str])
+    {
+        let mut dialog = rfd::FileDialog::new();
+        if !extensions.is_empty() {
+            dialog = dialog.add_filter(filter, extensions);
+        }
+        if let Some(path) = dialog.pick_file() {
+            match std::fs::read(&path) {
+                Ok(data) => {
+                    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                    self.push(action, PickedFile { name, path: Some(path), data });
+                }
+                Err(err) => self.status.error(format!("pick {}: {}", path.display(), err))
+            }
+        }
+    }
+
+    /// asks for a file to read, an empty `extensions` accepts any file
+    #[cfg(target_arch = "wasm32")]
+    pub fn pick(&self, ctx: &egui::Context, action: FileAction, filter: &str, extensions: &[&str])
+    {
+        let mut dialog = rfd::AsyncFileDialog::new();
+        if !extensions.is_empty() {
+            dialog = dialog.add_filter(filter, extensions);
+        }
+        let picked = self.picked.clone();
+        let ctx = ctx.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Some(file) = dialog.pick_file().await {
+                let data = file.read().await;
+                picked.borrow_mut().push((action, PickedFile { name: file.file_name(), path: None, data }));
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    /// queues a file as if it was picked, e.g. one dropped onto the window
+    pub fn push(&self, action: FileAction, file: PickedFile)
+    {
+        self.picked.borrow_mut().push((action, file));
+    }
+
+    /// files picked since the last call
+    pub fn take(&self) -> Vec<(FileAction, PickedFile)>
+    {
+        std::mem::take(&mut *self.picked.borrow_mut())
+    }
+
+    /// asks where to write `data`, the web build downloads it as `file_name`.
+    /// Returns the path, or on the web the name, it was saved as
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, file_name: &str, filter: &str, extensions: &[&str], data: &[u8]) -> Option<String>
+    {
+        let path = self.save_path(file_name, filter, extensions)?;
+        self.write(&path, data).then_some(path)
+    }
+
+    /// asks where to save a file, the web build has nowhere to ask for
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_path(&self, file_name: &str, filter: &str, extensions: &[&str]) -> Option<String>
+    {
+        let path = rfd::FileDialog::new().add_filter(filter, extensions).set_file_name(file_name).save_file()?;
+        Some(path.display().to_string())
+    }
+
+    /// asks where to save a file, the web build has nowhere to ask for
+    #[cfg(target_arch = "wasm32")]
+    pub fn save_path(&self, _file_name: &str, _filter: &str, _extensions: &[&str]) -> Option<String>
+    {
+        None
+    }
+
+    /// asks where to write `data`, the web build downloads it as `file_name`.
+    /// Returns the path, or on the web the name, it was saved as
+    #[cfg(target_arch = "wasm32")]
+    pub fn save(&self, file_name: &str, _filter: &str, _extensions: &[&str], data: &[u8]) -> Option<String>
+    {
+        self.write(file_name, data).then(|| file_name.to_owned())
+    }
+
+    /// writes `data` to `path`, the web build downloads it under the path's file name
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write(&self, path: &str, data: &[u8]) -> bool
+    {
+        match std::fs::write(path, data) {
+            Ok(()) => true,
+            Err(err) => {
+                self.status.error(format!("write {}: {}", path, err));
+                false
+            }
+        }
+    }
+
+    /// writes `data` to `path`, the web build downloads it under the path's file name
+    #[cfg(target_arch = "wasm32")]
+    pub fn write(&self, path: &str, data: &[u8]) -> bool
+    {
+        let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+        match download(name, data) {
+            Ok(()) => true,
+            Err(err) => {
+                self.status.error(format!("download {}: {:?}", name, err));
+                false
+            }
+        }
+    }
+}
+
+/// hands `data` to the browser as a Blob download
+#[cfg(target_arch = "wasm32")]
+fn download(file_name: &str, data: &[u8]) -> Result<(), wasm_bindgen::JsValue>
+{
+    use wasm_bindgen::JsCast;
+
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(data));
+    let blob = web_sys::Blob::new_with_u8_array_sequence(&parts)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+    let document = web_sys::window().and_then(|w| w.document()).ok_or("no document")?;
+    let anchor: web_sys::HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url)
+}
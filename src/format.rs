@@ -0,0 +1,223 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::codec::{TileMode, SizeError, encode_plane, decode_planes, check_buffers, sheet_sizes, tile_dimensions};
+
+/// size of one NES CHR ROM bank, two pattern tables of 256 tiles
+pub const CHR_BANK_SIZE: usize = 8192;
+
+/// Bitplane layout of encoded tiles, independent of the tile height given by `TileMode`.
+/// 8x16 tiles are stored as their top 8x8 tile followed by the bottom one, which is
+/// also the even/odd tile pair the NES PPU fetches for 8x16 sprites
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[repr(u8)]
+pub enum TileFormat
+{
+    /// Game Boy 2bpp, both bitplanes interleaved per row
+    Gb = 0,
+    /// NES CHR 2bpp, 8 bytes of the low bitplane followed by 8 bytes of the high bitplane
+    Nes = 1,
+    /// SNES 4bpp, planes 0/1 interleaved per row followed by planes 2/3 interleaved per row
+    Snes = 2,
+    /// Sega Master System / Game Gear 4bpp, all 4 planes interleaved per row
+    Sms = 3,
+    /// GBA 4bpp, packed nibbles with the left pixel in the low nibble
+    Gba = 4
+}
+
+impl TileFormat
+{
+    pub const ALL: [TileFormat; 5] = [TileFormat::Gb, TileFormat::Nes, TileFormat::Snes, TileFormat::Sms, TileFormat::Gba];
+
+    pub fn from_u8(value: u8) -> Option<TileFormat>
+    {
+        TileFormat::ALL.iter().copied().find(|f| *f as u8 == value)
+    }
+
+    pub fn name(self) -> &'static str
+    {
+        match self {
+            TileFormat::Gb => "GB 2bpp",
+            TileFormat::Nes => "NES CHR",
+            TileFormat::Snes => "SNES 4bpp",
+            TileFormat::Sms => "SMS/GG 4bpp",
+            TileFormat::Gba => "GBA 4bpp"
+        }
+    }
+
+    /// usual file extension of headerless tile data
+    pub fn extension(self) -> &'static str
+    {
+        match self {
+            TileFormat::Gb => "2bpp",
+            TileFormat::Nes => "chr",
+            TileFormat::Snes | TileFormat::Sms | TileFormat::Gba => "4bpp"
+        }
+    }
+
+    pub fn bits_per_pixel(self) -> u32
+    {
+        match self {
+            TileFormat::Gb | TileFormat::Nes => 2,
+            TileFormat::Snes | TileFormat::Sms | TileFormat::Gba => 4
+        }
+    }
+
+    /// number of palette entries a pixel can index
+    pub fn colors(self) -> usize
+    {
+        1 << self.bits_per_pixel()
+    }
+
+    /// encoded size of a single tile
+    pub fn bytes_per_tile(self, mode: TileMode) -> usize
+    {
+        self.bits_per_pixel() as usize * mode as usize
+    }
+
+    fn encode_8x8(self, pixels: &[u8], stride: usize, out: &mut [u8])
+    {
+        for y in 0..8 {
+            let row = &pixels[y*stride..][..8];
+            match self {
+                TileFormat::Gb => {
+                    out[2*y] = encode_plane(row, 0);
+                    out[2*y + 1] = encode_plane(row, 1);
+                }
+                TileFormat::Nes => {
+                    out[y] = encode_plane(row, 0);
+                    out[8 + y] = encode_plane(row, 1);
+                }
+                TileFormat::Snes => {
+                    out[2*y] = encode_plane(row, 0);
+                    out[2*y + 1] = encode_plane(row, 1);
+                    out[16 + 2*y] = encode_plane(row, 2);
+                    out[17 + 2*y] = encode_plane(row, 3);
+                }
+                TileFormat::Sms => {
+                    for plane in 0..4 {
+                        out[4*y + plane] = encode_plane(row, plane as u32);
+                    }
+                }
+                TileFormat::Gba => {
+                    for (x, pair) in row.chunks_exact(2).enumerate() {
+                        out[4*y + x] = (pair[0] & 0xf) | (pair[1] & 0xf) << 4;
+                    }
+                }
+            }
+        }
+    }
+
+    fn decode_8x8(self, data: &[u8], out: &mut [u8], stride: usize)
+    {
+        for y in 0..8 {
+            let row = &mut out[y*stride..][..8];
+            match self {
+                TileFormat::Gb => decode_planes(&data[2*y..2*y + 2], row),
+                TileFormat::Nes => decode_planes(&[data[y], data[8 + y]], row),
+                TileFormat::Snes => decode_planes(&[data[2*y], data[2*y + 1], data[16 + 2*y], data[17 + 2*y]], row),
+                TileFormat::Sms => decode_planes(&data[4*y..4*y + 4], row),
+                TileFormat::Gba => {
+                    for (x, &byte) in data[4*y..4*y + 4].iter().enumerate() {
+                        row[2*x] = byte & 0xf;
+                        row[2*x + 1] = byte >> 4;
+                    }
+                }
+            }
+        }
+    }
+
+    /// encodes one tile of `mode` height whose top left pixel is `pixels[0]`, rows are `stride` pixels apart
+    pub fn encode_tile(self, pixels: &[u8], stride: usize, mode: TileMode, out: &mut [u8])
+    {
+        let bytes = self.bytes_per_tile(TileMode::Y8);
+        for (half, tile) in out[..self.bytes_per_tile(mode)].chunks_exact_mut(bytes).enumerate() {
+            self.encode_8x8(&pixels[half*8*stride..], stride, tile);
+        }
+    }
+
+    /// decodes one tile of `mode` height into `out`, rows are `stride` pixels apart
+    pub fn decode_tile(self, data: &[u8], out: &mut [u8], stride: usize, mode: TileMode)
+    {
+        let bytes = self.bytes_per_tile(TileMode::Y8);
+        for (half, tile) in data[..self.bytes_per_tile(mode)].chunks_exact(bytes).enumerate() {
+            self.decode_8x8(tile, &mut out[half*8*stride..], stride);
+        }
+    }
+
+    /// w and h in number of pixels, `out` receives the tiles in export order.
+    /// Panics if w and h are not whole tiles or the buffers do not match them, `try_encode_into` returns an error instead
+    pub fn encode_into(self, data: &[u8], w: u32, h: u32, mode: TileMode, out: &mut [u8])
+    {
+        if let Err(err) = self.try_encode_into(data, w, h, mode, out) {
+            panic!("{}", err);
+        }
+    }
+
+    /// like `encode_into`, failing if w and h are not whole tiles or the buffers do not match them
+    pub fn try_encode_into(self, data: &[u8], w: u32, h: u32, mode: TileMode, out: &mut [u8]) -> Result<(), SizeError>
+    {
+        let ystep = mode as usize;
+        let width = w as usize;
+        let bytes_per_tile = self.bytes_per_tile(mode);
+        let (tiles_x, tiles_y) = tile_dimensions(w, h, mode)?;
+        let (bytes, pixels) = sheet_sizes(tiles_x, tiles_y, mode, bytes_per_tile)?;
+        check_buffers((pixels, data.len()), (bytes, out.len()))?;
+
+        for (t, tile) in out.chunks_exact_mut(bytes_per_tile).enumerate() {
+            let x = t % (width / 8);
+            let y = t / (width / 8);
+            self.encode_tile(&data[y*ystep*width + x*8..], width, mode, tile);
+        }
+        Ok(())
+    }
+
+    /// w and h number of tiles, `out` receives w*8 x h*mode pixels.
+    /// Panics if the buffers do not hold w x h tiles, `try_decode_into` returns an error instead
+    pub fn decode_into(self, data: &[u8], w: u32, h: u32, mode: TileMode, out: &mut [u8])
+    {
+        if let Err(err) = self.try_decode_into(data, w, h, mode, out) {
+            panic!("{}", err);
+        }
+    }
+
+    /// like `decode_into`, failing if `data` is not w x h tiles or `out` not their pixels
+    pub fn try_decode_into(self, data: &[u8], w: u32, h: u32, mode: TileMode, out: &mut [u8]) -> Result<(), SizeError>
+    {
+        let ystep = mode as usize;
+        let width = w as usize * 8;
+        let bytes_per_tile = self.bytes_per_tile(mode);
+        let (bytes, pixels) = sheet_sizes(w, h, mode, bytes_per_tile)?;
+        check_buffers((bytes, data.len()), (pixels, out.len()))?;
+
+        for (t, tile) in data.chunks_exact(bytes_per_tile).enumerate() {
+            let x = t % w as usize;
+            let y = t / w as usize;
+            self.decode_tile(tile, &mut out[y*ystep*width + x*8..], width, mode);
+        }
+        Ok(())
+    }
+
+    /// w and h in number of pixels
+    pub fn encode(self, data: &[u8], w: u32, h: u32, mode: TileMode) -> Vec<u8>
+    {
+        let mut tiles = vec![0; (w/8*h/mode as u32) as usize * self.bytes_per_tile(mode)];
+        self.encode_into(data, w, h, mode, &mut tiles);
+        tiles
+    }
+
+    /// w and h number of tiles
+    pub fn decode(self, data: &[u8], w: u32, h: u32, mode: TileMode) -> Vec<u8>
+    {
+        let mut pixels = vec![0; (w*8*h*mode as u32) as usize];
+        self.decode_into(data, w, h, mode, &mut pixels);
+        pixels
+    }
+}
+
+/// pads tile data with empty tiles to a whole number of 8KB CHR banks
+pub fn pad_to_chr_banks(data: &mut Vec<u8>)
+{
+    let banks = core::cmp::max((data.len() + CHR_BANK_SIZE - 1) / CHR_BANK_SIZE, 1);
+    data.resize(banks * CHR_BANK_SIZE, 0);
+}
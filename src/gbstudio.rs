@@ -0,0 +1,163 @@
+use std::fmt;
+use crate::codec::TileMode;
+use crate::convert;
+use crate::dedupe::dedupe;
+use crate::error::Error;
+use crate::sheet::TileSheet;
+
+/// the only colors GB Studio accepts in backgrounds, index 0 is the lightest
+pub const BACKGROUND_COLORS: [[u8; 4]; 4] = [
+    [0xe0, 0xf8, 0xcf, 0xff],
+    [0x86, 0xc0, 0x6c, 0xff],
+    [0x30, 0x68, 0x50, 0xff],
+    [0x07, 0x18, 0x21, 0xff]
+];
+
+/// sprite colors, index 0 is the transparency key, 1-3 the white, light green and black shades sprites are drawn in
+pub const SPRITE_COLORS: [[u8; 4]; 4] = [
+    [0x65, 0xff, 0x00, 0xff],
+    [0xe0, 0xf8, 0xcf, 0xff],
+    [0x86, 0xc0, 0x6c, 0xff],
+    [0x07, 0x18, 0x21, 0xff]
+];
+
+/// unique 8x8 tiles a background may use
+pub const MAX_BACKGROUND_TILES: usize = 192;
+/// backgrounds cover at least the screen
+pub const MIN_BACKGROUND_SIZE: (u32, u32) = (160, 144);
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum AssetKind
+{
+    Background,
+    /// 8x16 sprite tiles
+    Sprite
+}
+
+impl AssetKind
+{
+    pub fn name(self) -> &'static str
+    {
+        match self {
+            AssetKind::Background => "background",
+            AssetKind::Sprite => "sprite sheet"
+        }
+    }
+
+    pub fn colors(self) -> &'static [[u8; 4]; 4]
+    {
+        match self {
+            AssetKind::Background => &BACKGROUND_COLORS,
+            AssetKind::Sprite => &SPRITE_COLORS
+        }
+    }
+
+    /// whether palette index `index` maps to one of the kind's colors
+    fn allows(self, index: u8) -> bool
+    {
+        (index as usize) < self.colors().len()
+    }
+}
+
+/// A rule broken by the asset, tile coordinates are in 8x8 tiles
+#[derive(Clone, PartialEq, Debug)]
+pub enum Violation
+{
+    /// width and height have to be multiples of 8, sprite heights multiples of 16
+    Size { width: u32, height: u32 },
+    TooSmall { width: u32, height: u32 },
+    /// first pixel of the tile with a palette index the kind has no color for
+    Color { x: u32, y: u32, index: u8 },
+    /// first occurrence of a unique tile past the limit
+    TooManyTiles { x: u32, y: u32, unique: usize }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Size { width, height } => write!(f, "size {}x{} is not a multiple of the tile size", width, height),
+            Violation::TooSmall { width, height } => write!(f, "size {}x{} is smaller than the {}x{} screen", width, height, MIN_BACKGROUND_SIZE.0, MIN_BACKGROUND_SIZE.1),
+            Violation::Color { x, y, index } => write!(f, "tile ({}, {}) uses color {}", x, y, index),
+            Violation::TooManyTiles { x, y, unique } => write!(f, "tile ({}, {}) is unique tile {}, the limit is {}", x, y, unique, MAX_BACKGROUND_TILES)
+        }
+    }
+}
+
+/// Every rule an asset broke, returned instead of writing it
+#[derive(Clone, PartialEq, Debug)]
+pub struct Violations(pub Vec<Violation>);
+
+impl fmt::Display for Violations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} GB Studio rule(s) broken", self.0.len())?;
+        for violation in &self.0 {
+            write!(f, "\n  {}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Violations {}
+
+/// the sheet split into 8x8 tiles as GB Studio counts them, whatever the editor's tile mode
+fn tiles_8x8(sheet: &TileSheet) -> TileSheet
+{
+    TileSheet { mode: TileMode::Y8, ..sheet.clone() }
+}
+
+/// number of distinct 8x8 tiles
+pub fn unique_tiles(sheet: &TileSheet) -> usize
+{
+    dedupe(&tiles_8x8(sheet)).unique_count(TileMode::Y8.bytes_per_tile())
+}
+
+/// checks the sheet against GB Studio's rules for `kind`
+pub fn validate(sheet: &TileSheet, kind: AssetKind) -> Vec<Violation>
+{
+    let mut violations = Vec::new();
+    let tile_height = match kind {
+        AssetKind::Background => 8,
+        AssetKind::Sprite => 16
+    };
+    if sheet.width % 8 != 0 || sheet.height % tile_height != 0 {
+        violations.push(Violation::Size { width: sheet.width, height: sheet.height });
+    }
+    if kind == AssetKind::Background && (sheet.width < MIN_BACKGROUND_SIZE.0 || sheet.height < MIN_BACKGROUND_SIZE.1) {
+        violations.push(Violation::TooSmall { width: sheet.width, height: sheet.height });
+    }
+
+    let tiles = tiles_8x8(sheet);
+    for t in 0..tiles.tile_count() {
+        let (tx, ty) = tiles.tile_origin(t).unwrap_or_default();
+        let bad = (0..64).map(|p| tiles.get(tx + p % 8, ty + p / 8)).find(|index| !kind.allows(*index));
+        if let Some(index) = bad {
+            violations.push(Violation::Color { x: tx / 8, y: ty / 8, index });
+        }
+    }
+
+    if kind == AssetKind::Background {
+        let deduped = dedupe(&tiles);
+        let mut seen = 0;
+        for (t, &unique) in deduped.map.iter().enumerate() {
+            if unique as usize == seen {
+                seen += 1;
+                if seen > MAX_BACKGROUND_TILES {
+                    let (tx, ty) = tiles.tile_origin(t as u32).unwrap_or_default();
+                    violations.push(Violation::TooManyTiles { x: tx / 8, y: ty / 8, unique: seen });
+                }
+            }
+        }
+    }
+    violations
+}
+
+/// PNG in GB Studio's colors, fails with every broken rule instead of writing an asset GB Studio would reject
+pub fn export_png(sheet: &TileSheet, kind: AssetKind) -> Result<Vec<u8>, Error>
+{
+    let violations = validate(sheet, kind);
+    if !violations.is_empty() {
+        return Err(Violations(violations).into());
+    }
+    convert::encode_png(sheet, kind.colors())
+}
+
@@ -1,4 +1,44 @@
 #![warn(clippy::all, rust_2018_idioms)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+#[cfg(feature = "gui")]
 mod app;
+pub mod animation;
+#[cfg(feature = "std")]
+pub mod aseprite;
+pub mod codec;
+pub mod compress;
+#[cfg(feature = "std")]
+pub mod convert;
+#[cfg(feature = "std")]
+pub mod dedupe;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "gui")]
+mod files;
+pub mod format;
+#[cfg(feature = "std")]
+pub mod gbstudio;
+#[cfg(feature = "std")]
+pub mod project;
+pub mod rip;
+pub mod rom;
+pub mod share;
+pub mod sheet;
+#[cfg(feature = "gui")]
+pub mod status;
+#[cfg(feature = "std")]
+pub mod storage;
+#[cfg(feature = "std")]
+pub mod tiled;
+pub mod tilemap;
+pub mod tl;
+pub mod vram;
+#[cfg(feature = "gui")]
 pub use app::TemplateApp;
+#[cfg(feature = "std")]
+pub use convert::{convert, ExportFormat, ExportOptions, InputFormat};
+#[cfg(feature = "std")]
+pub use error::Error;
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use crate::animation::Animation;
+use crate::compress::Compression;
+use crate::convert::{self, ExportFormat, ExportOptions, InputFormat};
+use crate::error::Error;
+use crate::share::{base64url_decode, base64url_encode};
+use crate::storage::DocumentStorage;
+use crate::tilemap::Tilemap;
+
+/// A tile sheet stored as .tl file, paths are relative to the project file
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct TileDocument
+{
+    pub name: String,
+    pub path: String,
+    /// palette shown when the document is opened
+    #[serde(default)]
+    pub palette: Option<String>,
+    /// animations of the document's tiles
+    #[serde(default)]
+    pub animations: Vec<Animation>
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Palette
+{
+    pub name: String,
+    pub colors: Vec<[u8; 4]>
+}
+
+/// Tilemap stored in the project, indexing the tiles of a document
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct MapDocument
+{
+    pub name: String,
+    /// name of the tile document
+    pub tileset: String,
+    pub map: Tilemap
+}
+
+/// Output written by `Project::build`
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct ExportTarget
+{
+    /// name of the tile document
+    pub document: String,
+    pub path: String,
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub dedupe: bool,
+    #[serde(default = "no_compression")]
+    pub compression: Compression
+}
+
+fn no_compression() -> Compression
+{
+    Compression::None
+}
+
+/// Tile documents, palettes, maps and export targets of a game, saved as JSON
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct Project
+{
+    pub documents: Vec<TileDocument>,
+    pub palettes: Vec<Palette>,
+    pub maps: Vec<MapDocument>,
+    pub exports: Vec<ExportTarget>
+}
+
+impl Project
+{
+    pub fn from_json(json: &str) -> Result<Project, Error>
+    {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> String
+    {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Project, Error>
+    {
+        Project::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error>
+    {
+        Ok(std::fs::write(path, self.to_json())?)
+    }
+
+    pub fn document(&self, name: &str) -> Option<&TileDocument>
+    {
+        self.documents.iter().find(|d| d.name == name)
+    }
+
+    pub fn palette(&self, name: &str) -> Option<&Palette>
+    {
+        self.palettes.iter().find(|p| p.name == name)
+    }
+
+    /// writes every export target to `storage`, reading the documents from it relative to `project_path`.
+    /// Returns the written paths and sizes
+    pub fn build(&self, storage: &mut dyn DocumentStorage, project_path: &str) -> Result<Vec<(String, usize)>, Error>
+    {
+        let project_path = Path::new(project_path);
+        let mut written = Vec::with_capacity(self.exports.len());
+        for target in &self.exports {
+            let document = self.document(&target.document)
+                .ok_or_else(|| Error::Unsupported(format!("export {}: no document {}", target.path, target.document)))?;
+            let options = ExportOptions {
+                format: target.format,
+                dedupe: target.dedupe,
+                compression: target.compression,
+                ..Default::default()
+            };
+
+            let input = resolve(project_path, &document.path);
+            let asset = convert::load_bytes(&storage.read(&input.to_string_lossy())?, InputFormat::from_path(&input), &options)?;
+            let data = convert::prepare(asset, &options).encode(options.format)?;
+            let data = convert::compress(data, &options)?;
+
+            let out = resolve(project_path, &target.path).to_string_lossy().into_owned();
+            storage.write(&out, &data)?;
+            written.push((out, data.len()));
+        }
+        Ok(written)
+    }
+
+    /// packs the project and the documents it lists, read from `storage` relative to `project_path`
+    pub fn export_archive(&self, storage: &dyn DocumentStorage, project_path: &str) -> Result<Vec<u8>, Error>
+    {
+        let project_path = Path::new(project_path);
+        let name = project_path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| String::from("project.json"));
+
+        let mut files = BTreeMap::new();
+        files.insert(name.clone(), base64url_encode(self.to_json().as_bytes()));
+        for document in &self.documents {
+            if !is_contained(&document.path) {
+                return Err(Error::Unsupported(format!("archive: {} is outside the project directory", document.path)));
+            }
+            let data = storage.read(&resolve(project_path, &document.path).to_string_lossy())?;
+            files.insert(document.path.clone(), base64url_encode(&data));
+        }
+        Ok(serde_json::to_vec(&Archive { project: name, files })?)
+    }
+}
+
+/// A project file and its documents packed into one JSON file, file contents are base64url encoded
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Archive
+{
+    /// name of the project file in `files`
+    project: String,
+    /// paths relative to the project file
+    files: BTreeMap<String, String>
+}
+
+/// whether `path` stays inside the directory it is relative to
+fn is_contained(path: &str) -> bool
+{
+    Path::new(path).components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// unpacks an archive into `dir` of `storage`, returns the path of the project file and the project
+pub fn import_archive(storage: &mut dyn DocumentStorage, dir: &str, data: &[u8]) -> Result<(String, Project), Error>
+{
+    let archive: Archive = serde_json::from_slice(data)?;
+    let mut files = Vec::with_capacity(archive.files.len());
+    for (path, encoded) in &archive.files {
+        if !is_contained(path) {
+            return Err(Error::Unsupported(format!("archive: {} is outside the project directory", path)));
+        }
+        let data = base64url_decode(encoded).map_err(|err| Error::Unsupported(format!("archive: {}: {}", path, err)))?;
+        files.push((path, data));
+    }
+
+    let project = match files.iter().find(|(path, _)| **path == archive.project) {
+        Some((_, data)) => Project::from_json(&String::from_utf8_lossy(data))?,
+        None => return Err(Error::Unsupported(format!("archive: no project file {}", archive.project)))
+    };
+    for (path, data) in &files {
+        storage.write(&Path::new(dir).join(path).to_string_lossy(), data)?;
+    }
+    Ok((Path::new(dir).join(&archive.project).to_string_lossy().into_owned(), project))
+}
+
+/// `path` relative to the directory of the project file
+pub fn resolve(project_path: &Path, path: &str) -> PathBuf
+{
+    project_path.parent().unwrap_or(Path::new("")).join(path)
+}
+
+/// `path` relative to the directory of the project file if it is inside it
+pub fn relative(project_path: &Path, path: &Path) -> String
+{
+    let dir = project_path.parent().unwrap_or(Path::new(""));
+    path.strip_prefix(dir).unwrap_or(path).to_string_lossy().replace('\\', "/")
+}
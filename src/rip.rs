@@ -0,0 +1,75 @@
+use alloc::vec::Vec;
+use crate::codec::TileMode;
+use crate::format::TileFormat;
+use crate::sheet::TileSheet;
+
+/// Views arbitrary binary data, e.g. a ROM, as a grid of tiles starting at a byte offset
+#[derive(Clone, PartialEq, Debug)]
+pub struct TileRipper
+{
+    pub data: Vec<u8>,
+    /// byte offset of the first tile, need not be tile aligned
+    pub offset: usize,
+    pub tiles_per_row: u32,
+    pub mode: TileMode,
+    pub format: TileFormat
+}
+
+impl TileRipper
+{
+    pub fn new(data: Vec<u8>) -> Self
+    {
+        Self {
+            data,
+            offset: 0,
+            tiles_per_row: 16,
+            mode: TileMode::Y8,
+            format: TileFormat::Gb
+        }
+    }
+
+    pub fn bytes_per_tile(&self) -> usize
+    {
+        self.format.bytes_per_tile(self.mode)
+    }
+
+    /// number of whole tiles from `offset` to the end of the data
+    pub fn tile_count(&self) -> u32
+    {
+        (self.data.len().saturating_sub(self.offset) / self.bytes_per_tile()) as u32
+    }
+
+    /// number of tile rows, the last one may be partial
+    pub fn rows(&self) -> u32
+    {
+        let tiles_per_row = core::cmp::max(self.tiles_per_row, 1);
+        (self.tile_count() + tiles_per_row - 1) / tiles_per_row
+    }
+
+    /// byte address of tile t in the data
+    pub fn tile_address(&self, t: u32) -> usize
+    {
+        self.offset + t as usize * self.bytes_per_tile()
+    }
+
+    /// tile data of `count` tiles starting at tile `first`, clamped to the end of the data
+    pub fn tiles(&self, first: u32, count: u32) -> &[u8]
+    {
+        let start = core::cmp::min(self.tile_address(first), self.data.len());
+        let end = core::cmp::min(self.tile_address(first.saturating_add(count)), self.data.len());
+        &self.data[start..core::cmp::max(start, end)]
+    }
+
+    /// decodes `count` tiles starting at tile `first`, laid out `tiles_per_row` wide
+    pub fn extract(&self, first: u32, count: u32) -> TileSheet
+    {
+        TileSheet::from_tiles_as(self.format, self.tiles(first, count), self.tiles_per_row, self.mode)
+    }
+
+    /// decodes `count` tile rows starting at tile row `first_row`, for drawing the visible part of the grid
+    pub fn rows_sheet(&self, first_row: u32, count: u32) -> TileSheet
+    {
+        let tiles_per_row = core::cmp::max(self.tiles_per_row, 1);
+        self.extract(first_row * tiles_per_row, count * tiles_per_row)
+    }
+}
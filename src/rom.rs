@@ -0,0 +1,197 @@
+use core::fmt;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::sheet::{TileSheet, quantize_luma};
+use crate::tilemap::Tilemap;
+
+/// smallest ROM without a mapper
+pub const ROM_SIZE: usize = 0x8000;
+/// identity BG palette, color i shows shade i
+pub const DEFAULT_BGP: u8 = 0b11_10_01_00;
+pub const TITLE: &[u8] = b"RZRTILES";
+
+/// the boot ROM refuses to start a cartridge unless $0104-$0133 match this
+pub const NINTENDO_LOGO: [u8; 48] = [
+    0xce, 0xed, 0x66, 0x66, 0xcc, 0x0d, 0x00, 0x0b, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0c, 0x00, 0x0d,
+    0x00, 0x08, 0x11, 0x1f, 0x88, 0x89, 0x00, 0x0e, 0xdc, 0xcc, 0x6e, 0xe6, 0xdd, 0xdd, 0xd9, 0x99,
+    0xbb, 0xbb, 0x67, 0x63, 0x6e, 0x0e, 0xec, 0xcc, 0xdd, 0xdc, 0x99, 0x9f, 0xbb, 0xb9, 0x33, 0x3e
+];
+
+const LOGO: usize = 0x104;
+const TITLE_ADDR: usize = 0x134;
+const HEADER_CHECKSUM: usize = 0x14d;
+const GLOBAL_CHECKSUM: usize = 0x14e;
+const STUB: usize = 0x150;
+const MAP: usize = 0x200;
+const MAP_SIZE: usize = 32 * 32;
+const TILES: usize = MAP + MAP_SIZE;
+/// the whole $8000-$8FFF block is copied so no boot logo tiles are left behind
+const TILES_SIZE: usize = 256 * 16;
+const _: () = assert!(STUB + STUB_CODE.len() <= MAP && TILES + TILES_SIZE <= ROM_SIZE);
+
+/// Hand assembled SM83 code at $0150: waits for VBlank, turns the LCD off, copies the tiles
+/// to $8000 and the map to $9800, sets BGP and turns the LCD back on with only the BG enabled.
+/// The operands of `ld hl`/`ld bc` and `ld a, BGP` are patched in by `test_rom`
+const STUB_CODE: [u8; 62] = [
+    0xf3,             // $0150 di
+    0x31, 0xfe, 0xff, // $0151 ld sp, $FFFE
+    0xf0, 0x44,       // $0154 .vblank: ldh a, [rLY]
+    0xfe, 0x90,       // $0156 cp 144
+    0x38, 0xfa,       // $0158 jr c, .vblank
+    0xaf,             // $015A xor a
+    0xe0, 0x40,       // $015B ldh [rLCDC], a
+    0x21, 0x00, 0x00, // $015D ld hl, TILES
+    0x11, 0x00, 0x80, // $0160 ld de, $8000
+    0x01, 0x00, 0x00, // $0163 ld bc, TILES_SIZE
+    0xcd, 0x85, 0x01, // $0166 call .memcpy
+    0x21, 0x00, 0x00, // $0169 ld hl, MAP
+    0x11, 0x00, 0x98, // $016C ld de, $9800
+    0x01, 0x00, 0x00, // $016F ld bc, MAP_SIZE
+    0xcd, 0x85, 0x01, // $0172 call .memcpy
+    0x3e, 0x00,       // $0175 ld a, BGP
+    0xe0, 0x47,       // $0177 ldh [rBGP], a
+    0xaf,             // $0179 xor a
+    0xe0, 0x42,       // $017A ldh [rSCY], a
+    0xe0, 0x43,       // $017C ldh [rSCX], a
+    0x3e, 0x91,       // $017E ld a, LCDC_ON | LCDC_TILES_8000 | LCDC_BG_ON
+    0xe0, 0x40,       // $0180 ldh [rLCDC], a
+    0x76,             // $0182 .halt: halt
+    0x18, 0xfd,       // $0183 jr .halt
+    0x2a,             // $0185 .memcpy: ld a, [hl+]
+    0x12,             // $0186 ld [de], a
+    0x13,             // $0187 inc de
+    0x0b,             // $0188 dec bc
+    0x78,             // $0189 ld a, b
+    0xb1,             // $018A or c
+    0x20, 0xf8,       // $018B jr nz, .memcpy
+    0xc9              // $018D ret
+];
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum RomError
+{
+    /// the BG tile block holds 256 tiles, counted after flipping and removing duplicates
+    TooManyTiles
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RomError::TooManyTiles => write!(f, "the map uses more than 256 unique tiles")
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RomError {}
+
+/// BGP value showing palette index i with the DMG shade closest to `lumas[i]`
+pub fn bgp_from_luma(lumas: [u8; 4]) -> u8
+{
+    lumas.iter().enumerate().fold(0, |bgp, (i, luma)| bgp | quantize_luma(*luma) << (2 * i))
+}
+
+/// checksum the boot ROM verifies over $0134-$014C
+pub fn header_checksum(rom: &[u8]) -> u8
+{
+    rom[TITLE_ADDR..HEADER_CHECKSUM].iter().fold(0u8, |x, b| x.wrapping_sub(*b).wrapping_sub(1))
+}
+
+/// big endian sum of all bytes except the checksum itself, not checked by hardware
+pub fn global_checksum(rom: &[u8]) -> u16
+{
+    rom.iter().enumerate()
+        .filter(|(i, _)| *i != GLOBAL_CHECKSUM && *i != GLOBAL_CHECKSUM + 1)
+        .fold(0u16, |sum, (_, b)| sum.wrapping_add(*b as u16))
+}
+
+fn flip_tile(tile: &[u8], flip_x: bool, flip_y: bool) -> [u8; 16]
+{
+    let mut out = [0u8; 16];
+    for row in 0..8 {
+        let src = if flip_y { 7 - row } else { row };
+        for plane in 0..2 {
+            let byte = tile[2*src + plane];
+            out[2*row + plane] = if flip_x { byte.reverse_bits() } else { byte };
+        }
+    }
+    out
+}
+
+/// the top left 32x32 tiles of `map` with flips applied and duplicates removed,
+/// returns the tile block data and the map bytes
+fn build_vram(tileset: &TileSheet, map: &Tilemap) -> Result<(Vec<u8>, Vec<u8>), RomError>
+{
+    let tiles = tileset.export();
+    let mut unique: BTreeMap<[u8; 16], u8> = BTreeMap::new();
+    // empty map entries use the blank tile 0
+    unique.insert([0; 16], 0);
+    let mut tile_data = vec![0u8; TILES_SIZE];
+    let mut bg = vec![0u8; MAP_SIZE];
+
+    for y in 0..core::cmp::min(map.height, 32) {
+        for x in 0..core::cmp::min(map.width, 32) {
+            let cell = map.get(x, y);
+            let start = cell.tile as usize * 16;
+            let tile = match tiles.get(start..start + 16) {
+                Some(tile) => flip_tile(tile, cell.flip_x, cell.flip_y),
+                None => [0; 16]
+            };
+
+            let next = unique.len();
+            let index = match unique.get(&tile) {
+                Some(index) => *index,
+                None if next < 256 => {
+                    tile_data[next*16..][..16].copy_from_slice(&tile);
+                    unique.insert(tile, next as u8);
+                    next as u8
+                }
+                None => return Err(RomError::TooManyTiles)
+            };
+            bg[(y*32 + x) as usize] = index;
+        }
+    }
+
+    Ok((tile_data, bg))
+}
+
+/// 32KB ROM that shows `map` built from the 8x8 tiles of `tileset` with BG palette `bgp`,
+/// map entries index 8x8 tiles like `Tilemap::render`
+pub fn test_rom(tileset: &TileSheet, map: &Tilemap, bgp: u8) -> Result<Vec<u8>, RomError>
+{
+    let (tile_data, bg) = build_vram(tileset, map)?;
+    let mut rom = vec![0u8; ROM_SIZE];
+
+    // entry point: nop, jp $0150
+    rom[0x100..0x104].copy_from_slice(&[0x00, 0xc3, 0x50, 0x01]);
+    rom[LOGO..LOGO + NINTENDO_LOGO.len()].copy_from_slice(&NINTENDO_LOGO);
+    rom[TITLE_ADDR..TITLE_ADDR + TITLE.len()].copy_from_slice(TITLE);
+    // cartridge type ROM only, 32KB, no RAM, non-Japanese
+    rom[0x147] = 0x00;
+    rom[0x148] = 0x00;
+    rom[0x149] = 0x00;
+    rom[0x14a] = 0x01;
+
+    let stub = &mut rom[STUB..STUB + STUB_CODE.len()];
+    stub.copy_from_slice(&STUB_CODE);
+    stub[0x0e..0x10].copy_from_slice(&(TILES as u16).to_le_bytes());
+    stub[0x14..0x16].copy_from_slice(&(TILES_SIZE as u16).to_le_bytes());
+    stub[0x1a..0x1c].copy_from_slice(&(MAP as u16).to_le_bytes());
+    stub[0x20..0x22].copy_from_slice(&(MAP_SIZE as u16).to_le_bytes());
+    stub[0x26] = bgp;
+
+    rom[MAP..MAP + MAP_SIZE].copy_from_slice(&bg);
+    rom[TILES..TILES + TILES_SIZE].copy_from_slice(&tile_data);
+
+    rom[HEADER_CHECKSUM] = header_checksum(&rom);
+    let global = global_checksum(&rom);
+    rom[GLOBAL_CHECKSUM..GLOBAL_CHECKSUM + 2].copy_from_slice(&global.to_be_bytes());
+    Ok(rom)
+}
+
+/// test ROM showing the sheet as laid out in the editor
+pub fn sheet_rom(sheet: &TileSheet, bgp: u8) -> Result<Vec<u8>, RomError>
+{
+    test_rom(sheet, &Tilemap::from_sheet(sheet), bgp)
+}
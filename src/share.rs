@@ -0,0 +1,137 @@
+use core::fmt;
+use alloc::string::String;
+use alloc::vec::Vec;
+use crate::compress::{CompressError, Compression};
+use crate::format::TileFormat;
+use crate::sheet::TileSheet;
+use crate::tl::{self, TlError};
+
+/// where the web build is published, links made by native builds point there
+pub const WEB_URL: &str = "https://razor8.github.io/rzrTiles/";
+/// the URL fragment starts with this, followed by the encoded sheet
+pub const FRAGMENT_PREFIX: &str = "#tiles=";
+/// longest link `link` makes, browsers and chat apps cut or reject longer URLs
+pub const MAX_LINK_LEN: usize = 8000;
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ShareError
+{
+    /// character at the given position isn't base64url
+    Base64(usize),
+    Compression(CompressError),
+    /// the palette runs past the end of the data
+    Truncated,
+    Tl(TlError),
+    /// the link would be longer than `MAX_LINK_LEN`
+    TooLong(usize)
+}
+
+impl fmt::Display for ShareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShareError::Base64(at) => write!(f, "invalid character at {} in the link", at),
+            ShareError::Compression(err) => write!(f, "{}", err),
+            ShareError::Truncated => write!(f, "link truncated"),
+            ShareError::Tl(err) => write!(f, "{}", err),
+            ShareError::TooLong(len) => write!(f, "link would be {} characters long, at most {} are shared", len, MAX_LINK_LEN)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ShareError {}
+
+impl From<CompressError> for ShareError {
+    fn from(err: CompressError) -> Self {
+        ShareError::Compression(err)
+    }
+}
+
+impl From<TlError> for ShareError {
+    fn from(err: TlError) -> Self {
+        ShareError::Tl(err)
+    }
+}
+
+/// Sheet, format and palette decoded from a link
+#[derive(Clone, PartialEq, Debug)]
+pub struct Shared
+{
+    pub sheet: TileSheet,
+    pub format: TileFormat,
+    /// opaque RGB colors
+    pub palette: Vec<[u8; 3]>
+}
+
+/// base64url without padding
+pub fn base64url_encode(data: &[u8]) -> String
+{
+    let mut out = String::with_capacity((data.len() * 4 + 2) / 3);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, b)| bits | (*b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(BASE64URL[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+pub fn base64url_decode(text: &str) -> Result<Vec<u8>, ShareError>
+{
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for (i, c) in text.bytes().enumerate() {
+        let value = BASE64URL.iter().position(|b| *b == c).ok_or(ShareError::Base64(i))?;
+        bits = bits << 6 | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+    Ok(out)
+}
+
+/// the palette count and RGB colors followed by the sheet as .tl file, LZ77 compressed and base64url encoded
+pub fn encode(sheet: &TileSheet, format: TileFormat, palette: &[[u8; 3]]) -> Result<String, TlError>
+{
+    let colors = &palette[..core::cmp::min(palette.len(), format.colors())];
+    let mut data = Vec::with_capacity(1 + colors.len() * 3);
+    data.push(colors.len() as u8);
+    for color in colors {
+        data.extend_from_slice(color);
+    }
+    data.extend_from_slice(&tl::to_tl_as(sheet, format)?);
+    Ok(base64url_encode(&Compression::Lz77.compress(&data)))
+}
+
+pub fn decode(text: &str) -> Result<Shared, ShareError>
+{
+    let data = Compression::Lz77.decompress(&base64url_decode(text)?)?;
+    let count = *data.first().ok_or(ShareError::Truncated)? as usize;
+    let colors = data.get(1..1 + count * 3).ok_or(ShareError::Truncated)?;
+    let palette = colors.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect();
+    let (sheet, format) = tl::read_tl(&data[1 + count * 3..])?;
+    Ok(Shared { sheet, format, palette })
+}
+
+/// `base` with the encoded sheet as fragment, fails if that is longer than `MAX_LINK_LEN`
+pub fn link(base: &str, sheet: &TileSheet, format: TileFormat, palette: &[[u8; 3]]) -> Result<String, ShareError>
+{
+    let base = base.split('#').next().unwrap_or(base);
+    let link = [base, FRAGMENT_PREFIX, &encode(sheet, format, palette)?].concat();
+    if link.len() > MAX_LINK_LEN {
+        return Err(ShareError::TooLong(link.len()));
+    }
+    Ok(link)
+}
+
+/// the sheet in a link or its fragment, None if it doesn't carry one
+pub fn from_link(link: &str) -> Option<Result<Shared, ShareError>>
+{
+    let start = link.find(FRAGMENT_PREFIX)?;
+    Some(decode(&link[start + FRAGMENT_PREFIX.len()..]))
+}
@@ -0,0 +1,175 @@
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use image::{GrayImage, RgbaImage};
+use crate::codec::{TileMode, pixels_to_gb_tiles};
+use crate::format::TileFormat;
+
+/// Classic DMG green shades, lightest first
+pub const GB_SHADES: [[u8; 4]; 4] = [
+    [0xe0, 0xf8, 0xd0, 0xff],
+    [0x88, 0xc0, 0x70, 0xff],
+    [0x34, 0x68, 0x56, 0xff],
+    [0x08, 0x18, 0x20, 0xff]
+];
+
+/// Palette indexed pixels of a tile sheet, width is a multiple of 8
+/// and height a multiple of the tile mode
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct TileSheet
+{
+    pub pixels: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub mode: TileMode
+}
+
+impl Default for TileSheet {
+    fn default() -> Self {
+        Self::new(8, 8, TileMode::Y8)
+    }
+}
+
+impl TileSheet
+{
+    /// w and h in number of pixels
+    pub fn new(w: u32, h: u32, mode: TileMode) -> Self
+    {
+        Self {
+            pixels: vec![0; (w*h) as usize],
+            width: w,
+            height: h,
+            mode
+        }
+    }
+
+    /// raw 2bpp tile data laid out `tiles_x` tiles per row, a partial last row is padded with empty tiles
+    pub fn from_tiles(data: &[u8], tiles_x: u32, mode: TileMode) -> Self
+    {
+        Self::from_tiles_as(TileFormat::Gb, data, tiles_x, mode)
+    }
+
+    /// like `from_tiles` for tile data in `format`
+    pub fn from_tiles_as(format: TileFormat, data: &[u8], tiles_x: u32, mode: TileMode) -> Self
+    {
+        let bytes_per_tile = format.bytes_per_tile(mode);
+        let tiles = (data.len() / bytes_per_tile) as u32;
+        let tiles_x = core::cmp::max(core::cmp::min(tiles_x, tiles), 1);
+        let tiles_y = core::cmp::max((tiles + tiles_x - 1) / tiles_x, 1);
+
+        let mut padded = vec![0; (tiles_x * tiles_y) as usize * bytes_per_tile];
+        let used = tiles as usize * bytes_per_tile;
+        padded[..used].copy_from_slice(&data[..used]);
+
+        Self {
+            pixels: format.decode(&padded, tiles_x, tiles_y, mode),
+            width: tiles_x * 8,
+            height: tiles_y * mode as u32,
+            mode
+        }
+    }
+
+    /// quantizes luminance to the 4 shades, cropping to whole tiles
+    #[cfg(feature = "std")]
+    pub fn from_luma(img: &GrayImage, mode: TileMode) -> Self
+    {
+        let ystep = mode as u32;
+        let mut sheet = Self::new(img.width() / 8 * 8, img.height() / ystep * ystep, mode);
+        for y in 0..sheet.height {
+            for x in 0..sheet.width {
+                sheet.set(x, y, quantize_luma(img.get_pixel(x, y)[0]));
+            }
+        }
+        sheet
+    }
+
+    #[cfg(feature = "std")]
+    pub fn to_rgba(&self, palette: &[[u8; 4]]) -> RgbaImage
+    {
+        RgbaImage::from_fn(self.width, self.height, |x, y| {
+            image::Rgba(palette[self.get(x, y) as usize % palette.len()])
+        })
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> u8
+    {
+        let index = (y*self.width+x) as usize;
+        self.pixels[index]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, val: u8)
+    {
+        let index = (y*self.width+x) as usize;
+        self.pixels[index] = val;
+    }
+
+    pub fn tiles_x(&self) -> u32
+    {
+        self.width / 8
+    }
+
+    pub fn tiles_y(&self) -> u32
+    {
+        self.height / self.mode as u32
+    }
+
+    pub fn tile_count(&self) -> u32
+    {
+        self.tiles_x() * self.tiles_y()
+    }
+
+    /// top left pixel of tile t, tiles are numbered in export order
+    pub fn tile_origin(&self, t: u32) -> Option<(u32, u32)>
+    {
+        if t >= self.tile_count() {
+            return None;
+        }
+        let tiles_x = self.tiles_x();
+        Some(((t % tiles_x) * 8, (t / tiles_x) * self.mode as u32))
+    }
+
+    /// 2bpp encoded tiles in export order
+    pub fn export(&self) -> Vec<u8>
+    {
+        pixels_to_gb_tiles(&self.pixels, self.width, self.height, self.mode)
+    }
+
+    /// tiles in export order encoded as `format`
+    pub fn export_as(&self, format: TileFormat) -> Vec<u8>
+    {
+        format.encode(&self.pixels, self.width, self.height, self.mode)
+    }
+
+    /// copies the overlapping area into a sheet of the new size
+    pub fn resize(&mut self, width: u32, height: u32)
+    {
+        let mut new_pixels = vec![0; (width*height) as usize];
+        let miny = core::cmp::min(self.height, height);
+        let minx = core::cmp::min(self.width, width);
+
+        for i in 0..miny {
+            for j in 0..minx {
+                let dst= (i*width + j) as usize;
+                let src= (i*(self.width) + j) as usize;
+                new_pixels[dst] = self.pixels[src];
+            }
+        }
+
+        self.pixels = new_pixels;
+        self.width = width;
+        self.height = height;
+    }
+}
+
+/// maps an 8 bit luminance to palette index 0..=3, 0 being the lightest shade
+pub fn quantize_luma(luma: u8) -> u8
+{
+    quantize_luma_to(luma, 4)
+}
+
+/// maps an 8 bit luminance to palette index 0..colors, 0 being the lightest shade
+pub fn quantize_luma_to(luma: u8, colors: u8) -> u8
+{
+    colors - 1 - ((luma as u32 * colors as u32) / 256) as u8
+}
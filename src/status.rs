@@ -0,0 +1,58 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// messages kept for the status line, older ones are dropped
+const MAX_MESSAGES: usize = 20;
+
+/// A line for the status bar
+#[derive(Clone, PartialEq, Debug)]
+pub struct Message
+{
+    pub text: String,
+    pub error: bool
+}
+
+/// Errors and results of file operations, shown in the editor's status line and logged.
+/// Clones share the messages, so the callbacks of the web build can report once they finish
+#[derive(Clone, Default)]
+pub struct Status
+{
+    messages: Rc<RefCell<Vec<Message>>>
+}
+
+impl Status
+{
+    pub fn error(&self, text: impl Into<String>)
+    {
+        let text = text.into();
+        tracing::error!("{}", text);
+        self.push(Message { text, error: true });
+    }
+
+    pub fn info(&self, text: impl Into<String>)
+    {
+        let text = text.into();
+        tracing::info!("{}", text);
+        self.push(Message { text, error: false });
+    }
+
+    fn push(&self, message: Message)
+    {
+        let mut messages = self.messages.borrow_mut();
+        if messages.len() == MAX_MESSAGES {
+            messages.remove(0);
+        }
+        messages.push(message);
+    }
+
+    /// oldest first
+    pub fn messages(&self) -> Vec<Message>
+    {
+        self.messages.borrow().clone()
+    }
+
+    pub fn clear(&self)
+    {
+        self.messages.borrow_mut().clear();
+    }
+}
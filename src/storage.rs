@@ -0,0 +1,308 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use crate::error::Error;
+
+/// Where documents and projects are saved and loaded by name.
+/// Names are paths, relative ones use `/` and may include directories
+pub trait DocumentStorage
+{
+    /// names of the stored files, sorted
+    fn list(&self) -> Vec<String>;
+    fn read(&self, name: &str) -> Result<Vec<u8>, Error>;
+    /// creates or replaces `name`
+    fn write(&mut self, name: &str, data: &[u8]) -> Result<(), Error>;
+    fn remove(&mut self, name: &str) -> Result<(), Error>;
+    /// false while files are still being loaded, `list` and `read` may not know all of them yet
+    fn ready(&self) -> bool
+    {
+        true
+    }
+}
+
+fn not_found(name: &str) -> Error
+{
+    Error::Io(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", name)))
+}
+
+/// Files below `root`, names are paths relative to it or absolute
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct FileStorage
+{
+    pub root: PathBuf
+}
+
+impl FileStorage
+{
+    pub fn new(root: impl Into<PathBuf>) -> FileStorage
+    {
+        FileStorage { root: root.into() }
+    }
+
+    pub fn path(&self, name: &str) -> PathBuf
+    {
+        self.root.join(name)
+    }
+}
+
+impl DocumentStorage for FileStorage
+{
+    /// the files directly in `root`, not the ones in subdirectories
+    fn list(&self) -> Vec<String>
+    {
+        let dir = if self.root.as_os_str().is_empty() { PathBuf::from(".") } else { self.root.clone() };
+        let mut names: Vec<String> = std::fs::read_dir(dir).into_iter().flatten().flatten()
+            .filter(|entry| entry.file_type().map_or(false, |t| t.is_file()))
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn read(&self, name: &str) -> Result<Vec<u8>, Error>
+    {
+        Ok(std::fs::read(self.path(name))?)
+    }
+
+    /// creates missing parent directories
+    fn write(&mut self, name: &str, data: &[u8]) -> Result<(), Error>
+    {
+        let path = self.path(name);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        Ok(std::fs::write(path, data)?)
+    }
+
+    fn remove(&mut self, name: &str) -> Result<(), Error>
+    {
+        Ok(std::fs::remove_file(self.path(name))?)
+    }
+}
+
+/// Files kept in memory
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct MemoryStorage
+{
+    pub files: BTreeMap<String, Vec<u8>>
+}
+
+impl DocumentStorage for MemoryStorage
+{
+    fn list(&self) -> Vec<String>
+    {
+        self.files.keys().cloned().collect()
+    }
+
+    fn read(&self, name: &str) -> Result<Vec<u8>, Error>
+    {
+        self.files.get(name).cloned().ok_or_else(|| not_found(name))
+    }
+
+    fn write(&mut self, name: &str, data: &[u8]) -> Result<(), Error>
+    {
+        self.files.insert(name.to_owned(), data.to_vec());
+        Ok(())
+    }
+
+    fn remove(&mut self, name: &str) -> Result<(), Error>
+    {
+        self.files.remove(name).map(|_| ()).ok_or_else(|| not_found(name))
+    }
+}
+
+#[cfg(all(feature = "gui", target_arch = "wasm32"))]
+pub use indexed_db::IndexedDbStorage;
+
+#[cfg(all(feature = "gui", target_arch = "wasm32"))]
+mod indexed_db
+{
+    use std::cell::RefCell;
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::io;
+    use std::rc::Rc;
+    use wasm_bindgen::{JsCast, JsValue};
+    use web_sys::{IdbDatabase, IdbTransactionMode};
+    use crate::error::Error;
+    use crate::status::Status;
+    use super::{DocumentStorage, not_found};
+
+    const DATABASE: &str = "rzrtiles";
+    const STORE: &str = "documents";
+
+    fn js_error(err: JsValue) -> Error
+    {
+        Error::Io(io::Error::new(io::ErrorKind::Other, format!("IndexedDB: {:?}", err)))
+    }
+
+    /// Files in the browser's IndexedDB. Reads are served from a copy in memory that is
+    /// filled once the database is open, writes update it right away and the database in the background.
+    /// Failures in the background are reported to `status`
+    pub struct IndexedDbStorage
+    {
+        files: Rc<RefCell<BTreeMap<String, Vec<u8>>>>,
+        database: Rc<RefCell<Option<IdbDatabase>>>,
+        /// files removed before the stored ones were loaded, None once they are
+        removed: Rc<RefCell<Option<BTreeSet<String>>>>,
+        status: Status
+    }
+
+    impl IndexedDbStorage
+    {
+        /// starts opening the database, `ctx` is repainted once its files are loaded
+        pub fn open(ctx: &egui::Context, status: Status) -> IndexedDbStorage
+        {
+            let storage = IndexedDbStorage {
+                files: Rc::default(),
+                database: Rc::default(),
+                removed: Rc::new(RefCell::new(Some(BTreeSet::new()))),
+                status
+            };
+            if let Err(err) = storage.start(ctx.clone()) {
+                storage.status.error(format!("open storage: {:?}", err));
+                // nothing is going to be loaded
+                *storage.removed.borrow_mut() = None;
+            }
+            storage
+        }
+
+        fn start(&self, ctx: egui::Context) -> Result<(), JsValue>
+        {
+            let factory = web_sys::window().ok_or("no window")?.indexed_db()?.ok_or("no IndexedDB")?;
+            let request = factory.open_with_u32(DATABASE, 1)?;
+
+            let upgrading = request.clone();
+            let status = self.status.clone();
+            let upgrade = wasm_bindgen::closure::Closure::once_into_js(move || {
+                if let Ok(database) = upgrading.result() {
+                    if let Err(err) = database.unchecked_into::<IdbDatabase>().create_object_store(STORE) {
+                        status.error(format!("open storage: {:?}", err));
+                    }
+                }
+            });
+            request.set_onupgradeneeded(Some(upgrade.unchecked_ref()));
+
+            let opening = request.clone();
+            let files = self.files.clone();
+            let database = self.database.clone();
+            let removed = self.removed.clone();
+            let status = self.status.clone();
+            let success = wasm_bindgen::closure::Closure::once_into_js(move || {
+                let opened = opening.result().map(|db| db.unchecked_into::<IdbDatabase>());
+                match opened.and_then(|db| load(&db, files, removed.clone(), status.clone(), ctx).map(|()| db)) {
+                    Ok(db) => *database.borrow_mut() = Some(db),
+                    Err(err) => {
+                        status.error(format!("open storage: {:?}", err));
+                        *removed.borrow_mut() = None;
+                    }
+                }
+            });
+            request.set_onsuccess(Some(success.unchecked_ref()));
+            Ok(())
+        }
+
+        fn put(&self, name: &str, data: &[u8]) -> Result<(), JsValue>
+        {
+            if let Some(database) = &*self.database.borrow() {
+                let store = database.transaction_with_str_and_mode(STORE, IdbTransactionMode::Readwrite)?.object_store(STORE)?;
+                store.put_with_key(&js_sys::Uint8Array::from(data), &JsValue::from_str(name))?;
+            }
+            Ok(())
+        }
+
+        fn delete(&self, name: &str) -> Result<(), JsValue>
+        {
+            if let Some(database) = &*self.database.borrow() {
+                let store = database.transaction_with_str_and_mode(STORE, IdbTransactionMode::Readwrite)?.object_store(STORE)?;
+                store.delete(&JsValue::from_str(name))?;
+            }
+            Ok(())
+        }
+    }
+
+    /// writes and deletes the files saved and removed while the database was opening,
+    /// then reads all stored files into `files`
+    fn load(database: &IdbDatabase, files: Rc<RefCell<BTreeMap<String, Vec<u8>>>>, removed: Rc<RefCell<Option<BTreeSet<String>>>>, status: Status, ctx: egui::Context) -> Result<(), JsValue>
+    {
+        let transaction = database.transaction_with_str_and_mode(STORE, IdbTransactionMode::Readwrite)?;
+        let store = transaction.object_store(STORE)?;
+        for (name, data) in files.borrow().iter() {
+            store.put_with_key(&js_sys::Uint8Array::from(data.as_slice()), &JsValue::from_str(name))?;
+        }
+        for name in removed.borrow().iter().flatten() {
+            store.delete(&JsValue::from_str(name))?;
+        }
+        let keys = store.get_all_keys()?;
+        let values = store.get_all()?;
+
+        let failed = removed.clone();
+        let failing = transaction.clone();
+        let repaint = ctx.clone();
+        let error = wasm_bindgen::closure::Closure::once_into_js(move || {
+            status.error(format!("load storage: {:?}", failing.error()));
+            *failed.borrow_mut() = None;
+            repaint.request_repaint();
+        });
+        transaction.set_onerror(Some(error.unchecked_ref()));
+
+        let complete = wasm_bindgen::closure::Closure::once_into_js(move || {
+            // removes until now may have been read back before their delete ran
+            let removed = removed.borrow_mut().take().unwrap_or_default();
+            if let (Ok(keys), Ok(values)) = (keys.result(), values.result()) {
+                let keys: js_sys::Array = keys.unchecked_into();
+                let values: js_sys::Array = values.unchecked_into();
+                let mut files = files.borrow_mut();
+                for (key, value) in keys.iter().zip(values.iter()) {
+                    if let (Some(name), Ok(data)) = (key.as_string(), value.dyn_into::<js_sys::Uint8Array>()) {
+                        if !removed.contains(&name) {
+                            files.entry(name).or_insert_with(|| data.to_vec());
+                        }
+                    }
+                }
+            }
+            ctx.request_repaint();
+        });
+        transaction.set_oncomplete(Some(complete.unchecked_ref()));
+        Ok(())
+    }
+
+    impl DocumentStorage for IndexedDbStorage
+    {
+        fn list(&self) -> Vec<String>
+        {
+            self.files.borrow().keys().cloned().collect()
+        }
+
+        fn read(&self, name: &str) -> Result<Vec<u8>, Error>
+        {
+            self.files.borrow().get(name).cloned().ok_or_else(|| not_found(name))
+        }
+
+        fn write(&mut self, name: &str, data: &[u8]) -> Result<(), Error>
+        {
+            self.put(name, data).map_err(js_error)?;
+            self.files.borrow_mut().insert(name.to_owned(), data.to_vec());
+            if let Some(removed) = &mut *self.removed.borrow_mut() {
+                removed.remove(name);
+            }
+            Ok(())
+        }
+
+        fn remove(&mut self, name: &str) -> Result<(), Error>
+        {
+            self.delete(name).map_err(js_error)?;
+            // keeps the stored copy from being loaded back
+            if let Some(removed) = &mut *self.removed.borrow_mut() {
+                removed.insert(name.to_owned());
+                self.files.borrow_mut().remove(name);
+                return Ok(());
+            }
+            self.files.borrow_mut().remove(name).map(|_| ()).ok_or_else(|| not_found(name))
+        }
+
+        fn ready(&self) -> bool
+        {
+            self.removed.borrow().is_none()
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use std::fmt;
+use std::fmt::Write;
+use std::path::Path;
+use crate::codec::TileMode;
+use crate::convert::{self, ExportFormat};
+use crate::dedupe::dedupe;
+use crate::error::Error;
+use crate::sheet::TileSheet;
+use crate::tilemap::{MapCell, Tilemap};
+
+/// Tiled keeps the flips in the top bits of a global tile id
+pub const FLIP_X: u32 = 0x8000_0000;
+pub const FLIP_Y: u32 = 0x4000_0000;
+/// anti-diagonal flip, Tiled combines it with the other two for 90 degree rotations
+pub const FLIP_DIAGONAL: u32 = 0x2000_0000;
+/// all flag bits including the hexagonal 120 degree rotation
+const FLAGS: u32 = 0xf000_0000;
+/// tiles per row of exported atlases
+pub const ATLAS_COLUMNS: u32 = 16;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum TiledError
+{
+    Xml(String),
+    Json(String),
+    /// element or attribute the map can't do without
+    Missing(&'static str),
+    /// number that is negative or does not fit 32 bits
+    OutOfRange(&'static str),
+    /// only 8x8 tiles map to the Game Boy
+    TileSize { width: u32, height: u32 },
+    /// layer encodings, compression and infinite maps
+    Unsupported(String),
+    /// rotated tiles can't be shown by the Game Boy
+    Rotated { x: u32, y: u32 },
+    TileOutOfRange { x: u32, y: u32, gid: u32 },
+    /// maps need at least one tile
+    Empty { width: u32, height: u32 }
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiledError::Xml(err) => write!(f, "invalid XML: {}", err),
+            TiledError::Json(err) => write!(f, "invalid JSON: {}", err),
+            TiledError::Missing(what) => write!(f, "missing {}", what),
+            TiledError::OutOfRange(what) => write!(f, "{} out of range", what),
+            TiledError::TileSize { width, height } => write!(f, "tiles are {}x{}, only 8x8 tiles are supported", width, height),
+            TiledError::Unsupported(what) => write!(f, "unsupported: {}", what),
+            TiledError::Rotated { x, y } => write!(f, "tile at ({}, {}) is rotated", x, y),
+            TiledError::TileOutOfRange { x, y, gid } => write!(f, "tile at ({}, {}) references gid {} outside the tileset", x, y, gid),
+            TiledError::Empty { width, height } => write!(f, "map is {}x{} tiles", width, height)
+        }
+    }
+}
+
+impl std::error::Error for TiledError {}
+
+/// Tileset image and layout from a .tsx file or a tileset embedded in a map
+#[derive(Clone, PartialEq, Debug)]
+pub struct TiledTileset
+{
+    /// relative to the file the tileset is defined in
+    pub image: String,
+    pub columns: u32,
+    pub tile_count: u32
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum TilesetSource
+{
+    /// path of a .tsx file relative to the map
+    External(String),
+    Embedded(TiledTileset)
+}
+
+/// First tile layer and first tileset of a .tmx or .tmj map
+#[derive(Clone, PartialEq, Debug)]
+pub struct TiledMap
+{
+    /// width and height in tiles
+    pub width: u32,
+    pub height: u32,
+    pub firstgid: u32,
+    pub tileset: TilesetSource,
+    /// global tile ids including the flip flags, 0 is an empty cell
+    pub gids: Vec<u32>
+}
+
+/// global tile id of a map cell, palette and priority have no Tiled equivalent
+pub fn to_gid(cell: MapCell, firstgid: u32) -> u32
+{
+    let mut gid = cell.tile as u32 + firstgid;
+    if cell.flip_x {
+        gid |= FLIP_X;
+    }
+    if cell.flip_y {
+        gid |= FLIP_Y;
+    }
+    gid
+}
+
+/// map cell of a global tile id, empty cells show tile 0.
+/// None for rotated tiles and tile indices a map cell can't hold
+pub fn from_gid(gid: u32, firstgid: u32) -> Option<MapCell>
+{
+    if gid & FLIP_DIAGONAL != 0 {
+        return None;
+    }
+    let tile = u16::try_from((gid & !FLAGS).saturating_sub(firstgid)).ok()?;
+    Some(MapCell {
+        tile,
+        flip_x: gid & FLIP_X != 0,
+        flip_y: gid & FLIP_Y != 0,
+        ..Default::default()
+    })
+}
+
+/// 8x8 tiles of `sheet` in tilemap index order, 8x16 tiles are split into their top and bottom halves
+pub fn atlas(sheet: &TileSheet) -> TileSheet
+{
+    TileSheet::from_tiles(&sheet.export(), ATLAS_COLUMNS, TileMode::Y8)
+}
+
+fn escape(text: &str) -> String
+{
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// .tsx tileset of an `atlas` stored as `image`
+pub fn tsx(name: &str, image: &str, atlas: &TileSheet) -> String
+{
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <tileset version=\"1.10\" name=\"{}\" tilewidth=\"8\" tileheight=\"8\" tilecount=\"{}\" columns=\"{}\">\n \
+         <image source=\"{}\" width=\"{}\" height=\"{}\"/>\n\
+         </tileset>\n",
+        escape(name), atlas.tile_count(), atlas.tiles_x(), escape(image), atlas.width, atlas.height)
+}
+
+fn check_map_size(width: u32, height: u32) -> Result<(), TiledError>
+{
+    if width == 0 || height == 0 {
+        return Err(TiledError::Empty { width, height });
+    }
+    Ok(())
+}
+
+/// .tmx map with a single CSV encoded layer referencing the .tsx file `tileset`
+pub fn tmx(map: &Tilemap, tileset: &str) -> Result<String, TiledError>
+{
+    check_map_size(map.width, map.height)?;
+    let mut xml = String::new();
+    let _ = writeln!(xml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    let _ = writeln!(xml, "<map version=\"1.10\" orientation=\"orthogonal\" renderorder=\"right-down\" width=\"{w}\" height=\"{h}\" tilewidth=\"8\" tileheight=\"8\" infinite=\"0\" nextlayerid=\"2\" nextobjectid=\"1\">", w = map.width, h = map.height);
+    let _ = writeln!(xml, " <tileset firstgid=\"1\" source=\"{}\"/>", escape(tileset));
+    let _ = writeln!(xml, " <layer id=\"1\" name=\"Tiles\" width=\"{}\" height=\"{}\">", map.width, map.height);
+    let _ = writeln!(xml, "  <data encoding=\"csv\">");
+    for (y, row) in map.cells.chunks_exact(map.width as usize).enumerate() {
+        let gids: Vec<String> = row.iter().map(|cell| to_gid(*cell, 1).to_string()).collect();
+        let last = y + 1 == map.height as usize;
+        let _ = writeln!(xml, "{}{}", gids.join(","), if last { "" } else { "," });
+    }
+    let _ = writeln!(xml, "  </data>");
+    let _ = writeln!(xml, " </layer>");
+    let _ = writeln!(xml, "</map>");
+    Ok(xml)
+}
+
+/// .tmj map with a single layer referencing the .tsx file `tileset`
+pub fn tmj(map: &Tilemap, tileset: &str) -> Result<String, TiledError>
+{
+    check_map_size(map.width, map.height)?;
+    let data: Vec<u32> = map.cells.iter().map(|cell| to_gid(*cell, 1)).collect();
+    let json = serde_json::json!({
+        "type": "map",
+        "version": "1.10",
+        "orientation": "orthogonal",
+        "renderorder": "right-down",
+        "width": map.width,
+        "height": map.height,
+        "tilewidth": 8,
+        "tileheight": 8,
+        "infinite": false,
+        "nextlayerid": 2,
+        "nextobjectid": 1,
+        "tilesets": [{ "firstgid": 1, "source": tileset }],
+        "layers": [{
+            "id": 1,
+            "name": "Tiles",
+            "type": "tilelayer",
+            "x": 0,
+            "y": 0,
+            "width": map.width,
+            "height": map.height,
+            "opacity": 1,
+            "visible": true,
+            "data": data
+        }]
+    });
+    Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+}
+
+fn check_tile_size(width: u32, height: u32) -> Result<(), TiledError>
+{
+    if width != 8 || height != 8 {
+        return Err(TiledError::TileSize { width, height });
+    }
+    Ok(())
+}
+
+fn attribute(node: roxmltree::Node<'_, '_>, name: &'static str) -> Result<u32, TiledError>
+{
+    node.attribute(name).and_then(|v| v.parse().ok()).ok_or(TiledError::Missing(name))
+}
+
+fn xml_tileset(node: roxmltree::Node<'_, '_>) -> Result<TiledTileset, TiledError>
+{
+    check_tile_size(attribute(node, "tilewidth")?, attribute(node, "tileheight")?)?;
+    let image = node.children().find(|n| n.has_tag_name("image")).ok_or(TiledError::Missing("image"))?;
+    Ok(TiledTileset {
+        image: image.attribute("source").ok_or(TiledError::Missing("source"))?.to_owned(),
+        columns: attribute(node, "columns")?,
+        tile_count: attribute(node, "tilecount")?
+    })
+}
+
+/// parses a .tsx tileset
+pub fn parse_tsx(xml: &str) -> Result<TiledTileset, TiledError>
+{
+    let doc = roxmltree::Document::parse(xml).map_err(|e| TiledError::Xml(e.to_string()))?;
+    xml_tileset(doc.root_element())
+}
+
+/// parses the first tileset and tile layer of a .tmx map, layers have to be CSV or XML encoded
+pub fn parse_tmx(xml: &str) -> Result<TiledMap, TiledError>
+{
+    let doc = roxmltree::Document::parse(xml).map_err(|e| TiledError::Xml(e.to_string()))?;
+    let map = doc.root_element();
+    if map.attribute("infinite") == Some("1") {
+        return Err(TiledError::Unsupported("infinite maps".to_owned()));
+    }
+    check_tile_size(attribute(map, "tilewidth")?, attribute(map, "tileheight")?)?;
+
+    let tileset = map.children().find(|n| n.has_tag_name("tileset")).ok_or(TiledError::Missing("tileset"))?;
+    let source = match tileset.attribute("source") {
+        Some(source) => TilesetSource::External(source.to_owned()),
+        None => TilesetSource::Embedded(xml_tileset(tileset)?)
+    };
+
+    let layer = map.children().find(|n| n.has_tag_name("layer")).ok_or(TiledError::Missing("layer"))?;
+    let data = layer.children().find(|n| n.has_tag_name("data")).ok_or(TiledError::Missing("data"))?;
+    if let Some(compression) = data.attribute("compression") {
+        return Err(TiledError::Unsupported(format!("{} compressed layers", compression)));
+    }
+    let gids = match data.attribute("encoding") {
+        Some("csv") => data.text().unwrap_or("")
+            .split(',')
+            .map(|gid| gid.trim().parse().map_err(|_| TiledError::Unsupported(format!("gid {}", gid.trim()))))
+            .collect::<Result<Vec<u32>, _>>()?,
+        None => data.children().filter(|n| n.has_tag_name("tile")).map(|tile| tile.attribute("gid").and_then(|v| v.parse().ok()).unwrap_or(0)).collect(),
+        Some(encoding) => return Err(TiledError::Unsupported(format!("{} encoded layers", encoding)))
+    };
+
+    let (width, height) = (attribute(layer, "width")?, attribute(layer, "height")?);
+    check_map_size(width, height)?;
+    Ok(TiledMap {
+        width,
+        height,
+        firstgid: attribute(tileset, "firstgid")?,
+        tileset: source,
+        gids
+    })
+}
+
+fn json_u32(value: &serde_json::Value, name: &'static str) -> Result<u32, TiledError>
+{
+    let value = value.get(name).filter(|v| v.is_number()).ok_or(TiledError::Missing(name))?;
+    value.as_u64().and_then(|v| u32::try_from(v).ok()).ok_or(TiledError::OutOfRange(name))
+}
+
+/// parses the first tileset and tile layer of a .tmj map, layers have to be stored as arrays
+pub fn parse_tmj(json: &str) -> Result<TiledMap, TiledError>
+{
+    let map: serde_json::Value = serde_json::from_str(json).map_err(|e| TiledError::Json(e.to_string()))?;
+    if map.get("infinite").and_then(|v| v.as_bool()) == Some(true) {
+        return Err(TiledError::Unsupported("infinite maps".to_owned()));
+    }
+    check_tile_size(json_u32(&map, "tilewidth")?, json_u32(&map, "tileheight")?)?;
+
+    let tileset = map.get("tilesets").and_then(|t| t.get(0)).ok_or(TiledError::Missing("tilesets"))?;
+    let source = match tileset.get("source").and_then(|s| s.as_str()) {
+        Some(source) => TilesetSource::External(source.to_owned()),
+        None => {
+            check_tile_size(json_u32(tileset, "tilewidth")?, json_u32(tileset, "tileheight")?)?;
+            TilesetSource::Embedded(TiledTileset {
+                image: tileset.get("image").and_then(|i| i.as_str()).ok_or(TiledError::Missing("image"))?.to_owned(),
+                columns: json_u32(tileset, "columns")?,
+                tile_count: json_u32(tileset, "tilecount")?
+            })
+        }
+    };
+
+    let layer = map.get("layers").and_then(|l| l.as_array())
+        .and_then(|layers| layers.iter().find(|l| l.get("type").and_then(|t| t.as_str()) == Some("tilelayer")))
+        .ok_or(TiledError::Missing("layers"))?;
+    if let Some(encoding) = layer.get("encoding").and_then(|e| e.as_str()).filter(|e| *e != "csv") {
+        return Err(TiledError::Unsupported(format!("{} encoded layers", encoding)));
+    }
+    let gids = layer.get("data").and_then(|d| d.as_array()).ok_or(TiledError::Missing("data"))?
+        .iter()
+        .map(|gid| match gid.as_u64() {
+            Some(g) => u32::try_from(g).map_err(|_| TiledError::OutOfRange("data")),
+            None if gid.is_number() => Err(TiledError::OutOfRange("data")),
+            None => Err(TiledError::Missing("data"))
+        })
+        .collect::<Result<Vec<u32>, _>>()?;
+
+    let (width, height) = (json_u32(layer, "width")?, json_u32(layer, "height")?);
+    check_map_size(width, height)?;
+    Ok(TiledMap {
+        width,
+        height,
+        firstgid: json_u32(tileset, "firstgid")?,
+        tileset: source,
+        gids
+    })
+}
+
+/// deduplicated tileset and the tilemap indexing it, `atlas` holds the tiles of the map's tileset
+pub fn rebuild(map: &TiledMap, atlas: &TileSheet) -> Result<(TileSheet, Tilemap), TiledError>
+{
+    check_map_size(map.width, map.height)?;
+    if map.gids.len() as u64 != map.width as u64 * map.height as u64 {
+        return Err(TiledError::Missing("data"));
+    }
+
+    let deduped = dedupe(atlas);
+    let mut tilemap = Tilemap::new(map.width, map.height);
+    for (i, &gid) in map.gids.iter().enumerate() {
+        let (x, y) = (i as u32 % map.width, i as u32 / map.width);
+        let out_of_range = TiledError::TileOutOfRange { x, y, gid: gid & !FLAGS };
+        let cell = match from_gid(gid, map.firstgid) {
+            Some(cell) => cell,
+            None if gid & FLIP_DIAGONAL != 0 => return Err(TiledError::Rotated { x, y }),
+            None => return Err(out_of_range)
+        };
+        let unique = *deduped.map.get(cell.tile as usize).ok_or(out_of_range)?;
+        tilemap.set(x, y, MapCell { tile: unique as u16, ..cell });
+    }
+
+    let tileset = TileSheet::from_tiles(&deduped.tiles, ATLAS_COLUMNS, TileMode::Y8);
+    Ok((tileset, tilemap))
+}
+
+/// writes the map to a .tmx or .tmj `path`, the tileset goes next to it as .tsx and .png of the same name
+pub fn export(sheet: &TileSheet, map: &Tilemap, path: impl AsRef<Path>) -> Result<(), Error>
+{
+    let path = path.as_ref();
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("tiles");
+    let (tsx_name, png_name) = (format!("{}.tsx", stem), format!("{}.png", stem));
+
+    let atlas = atlas(sheet);
+    std::fs::write(path.with_file_name(&png_name), convert::encode(&atlas, ExportFormat::Png)?)?;
+    std::fs::write(path.with_file_name(&tsx_name), tsx(stem, &png_name, &atlas))?;
+
+    let data = match path.extension().and_then(|e| e.to_str()) {
+        Some("tmj") | Some("json") => tmj(map, &tsx_name)?,
+        _ => tmx(map, &tsx_name)?
+    };
+    std::fs::write(path, data)?;
+    Ok(())
+}
+
+/// imports a .tmx or .tmj map with its tileset image, the image is quantized by luminance
+pub fn import(path: impl AsRef<Path>) -> Result<(TileSheet, Tilemap), Error>
+{
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)?;
+    let map = match path.extension().and_then(|e| e.to_str()) {
+        Some("tmj") | Some("json") => parse_tmj(&text)?,
+        _ => parse_tmx(&text)?
+    };
+
+    // image paths are relative to the file defining the tileset
+    let (tileset, image) = match &map.tileset {
+        TilesetSource::Embedded(tileset) => (tileset.clone(), path.with_file_name(&tileset.image)),
+        TilesetSource::External(source) => {
+            let tsx_path = path.with_file_name(source);
+            let tileset = parse_tsx(&std::fs::read_to_string(&tsx_path)?)?;
+            let image = tsx_path.with_file_name(&tileset.image);
+            (tileset, image)
+        }
+    };
+
+    let img = image::open(image)?;
+    let mut atlas = TileSheet::from_luma(&img.to_luma8(), TileMode::Y8);
+    // margins and spacing are not supported, the image is cut into the tileset's columns
+    if tileset.columns > 0 && tileset.columns * 8 < atlas.width {
+        atlas.resize(tileset.columns * 8, atlas.height);
+    }
+    let mut tiles = atlas.export();
+    tiles.truncate(tileset.tile_count as usize * TileMode::Y8.bytes_per_tile());
+    let atlas = TileSheet::from_tiles(&tiles, std::cmp::max(tileset.columns, 1), TileMode::Y8);
+    Ok(rebuild(&map, &atlas)?)
+}
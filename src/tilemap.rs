@@ -0,0 +1,98 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::codec::TileMode;
+use crate::sheet::TileSheet;
+
+/// One entry of a tilemap, the flags mirror the CGB BG map attributes
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct MapCell
+{
+    /// index into the tileset in export order
+    pub tile: u16,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub palette: u8,
+    /// BG over OBJ priority
+    pub priority: bool
+}
+
+/// Grid of 8x8 tiles referencing a tileset, e.g. a Game Boy BG map
+#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Tilemap
+{
+    /// width and height in tiles
+    pub width: u32,
+    pub height: u32,
+    pub cells: Vec<MapCell>
+}
+
+impl Tilemap
+{
+    /// w and h in number of tiles
+    pub fn new(w: u32, h: u32) -> Self
+    {
+        Self {
+            width: w,
+            height: h,
+            cells: vec![MapCell::default(); (w*h) as usize]
+        }
+    }
+
+    /// map showing the 8x8 tiles of `sheet` where they are in the sheet
+    pub fn from_sheet(sheet: &TileSheet) -> Self
+    {
+        // 8x16 tiles count as their top and bottom 8x8 halves
+        let halves = sheet.mode as u32 / 8;
+        let mut map = Tilemap::new(sheet.tiles_x(), sheet.height / 8);
+        for y in 0..map.height {
+            for x in 0..map.width {
+                let tile = ((y / halves) * map.width + x) * halves + y % halves;
+                map.set(x, y, MapCell { tile: tile as u16, ..Default::default() });
+            }
+        }
+        map
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> MapCell
+    {
+        self.cells[(y*self.width + x) as usize]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, cell: MapCell)
+    {
+        self.cells[(y*self.width + x) as usize] = cell;
+    }
+
+    /// composes the map from the 8x8 tiles of `tileset`, tiles missing from the set stay empty
+    pub fn render(&self, tileset: &TileSheet) -> TileSheet
+    {
+        let mut out = TileSheet::new(self.width * 8, self.height * 8, TileMode::Y8);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let cell = self.get(x, y);
+                let (tx, ty) = match tile_origin_8x8(tileset, cell.tile as u32) {
+                    Some(origin) => origin,
+                    None => continue
+                };
+
+                for py in 0..8 {
+                    for px in 0..8 {
+                        let sx = if cell.flip_x { 7 - px } else { px };
+                        let sy = if cell.flip_y { 7 - py } else { py };
+                        out.set(x*8 + px, y*8 + py, tileset.get(tx + sx, ty + sy));
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// top left pixel of 8x8 tile t, 8x16 sheets count their top and bottom halves as separate tiles
+fn tile_origin_8x8(tileset: &TileSheet, t: u32) -> Option<(u32, u32)>
+{
+    let (x, y) = tileset.tile_origin(t / (tileset.mode as u32 / 8))?;
+    Some((x, y + (t % (tileset.mode as u32 / 8)) * 8))
+}
@@ -0,0 +1,163 @@
+use core::fmt;
+use alloc::vec::Vec;
+use crate::codec::TileMode;
+use crate::format::TileFormat;
+use crate::sheet::TileSheet;
+
+pub const TL_MAGIC: &[u8] = &[0x72,0x54, 0x69, 0x6c]; // rTil
+/// version 1 has no format byte and always stores GB 2bpp tiles
+pub const TL_VERSION: u8 = 2;
+/// magic, version, mode, width, height
+pub const TL_V1_HEADER_SIZE: usize = 8;
+/// magic, version, mode, width, height, format
+pub const TL_HEADER_SIZE: usize = 9;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum TlError
+{
+    Truncated { expected: usize, actual: usize },
+    BadMagic,
+    UnsupportedVersion(u8),
+    BadMode(u8),
+    BadFormat(u8),
+    BadDimensions { width: u32, height: u32 }
+}
+
+impl fmt::Display for TlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlError::Truncated { expected, actual } => write!(f, "file truncated, expected {} bytes but got {}", expected, actual),
+            TlError::BadMagic => write!(f, "not a .tl file (bad magic)"),
+            TlError::UnsupportedVersion(v) => write!(f, "unsupported .tl version {}", v),
+            TlError::BadMode(m) => write!(f, "unsupported tile mode {}", m),
+            TlError::BadFormat(t) => write!(f, "unsupported tile format {}", t),
+            TlError::BadDimensions { width, height } => write!(f, "invalid dimensions {}x{}", width, height)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TlError {}
+
+/// Decoded .tl header, width and height are in units of 8 pixels
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TlHeader
+{
+    pub version: u8,
+    pub mode: TileMode,
+    pub width: u8,
+    pub height: u8,
+    pub format: TileFormat
+}
+
+impl TlHeader
+{
+    pub fn parse(data: &[u8]) -> Result<TlHeader, TlError>
+    {
+        if data.len() < TL_V1_HEADER_SIZE {
+            return Err(TlError::Truncated { expected: TL_V1_HEADER_SIZE, actual: data.len() });
+        }
+        if &data[0..4] != TL_MAGIC {
+            return Err(TlError::BadMagic);
+        }
+        let mode = TileMode::from_u8(data[5]).ok_or(TlError::BadMode(data[5]))?;
+
+        let format = match data[4] {
+            1 => TileFormat::Gb,
+            TL_VERSION => {
+                let format = *data.get(8).ok_or(TlError::Truncated { expected: TL_HEADER_SIZE, actual: data.len() })?;
+                TileFormat::from_u8(format).ok_or(TlError::BadFormat(format))?
+            }
+            version => return Err(TlError::UnsupportedVersion(version))
+        };
+
+        Ok(TlHeader { version: data[4], mode, width: data[6], height: data[7], format })
+    }
+
+    /// size of the header in bytes
+    pub fn size(&self) -> usize
+    {
+        if self.version == 1 { TL_V1_HEADER_SIZE } else { TL_HEADER_SIZE }
+    }
+
+    pub fn tiles_x(&self) -> u32
+    {
+        self.width as u32
+    }
+
+    /// number of tile rows, the stored height counts 8 pixel rows regardless of mode
+    pub fn tiles_y(&self) -> u32
+    {
+        self.height as u32 * 8 / self.mode as u32
+    }
+
+    pub fn tile_count(&self) -> u32
+    {
+        self.tiles_x() * self.tiles_y()
+    }
+
+    /// size of the tile data following the header
+    pub fn data_size(&self) -> usize
+    {
+        self.tile_count() as usize * self.format.bytes_per_tile(self.mode)
+    }
+}
+
+/// parses a complete .tl file
+pub fn read_tl(data: &[u8]) -> Result<(TileSheet, TileFormat), TlError>
+{
+    let header = TlHeader::parse(data)?;
+    if header.width == 0 || header.height == 0 || (header.height as u32 * 8) % header.mode as u32 != 0 {
+        return Err(TlError::BadDimensions { width: header.width as u32 * 8, height: header.height as u32 * 8 });
+    }
+
+    let expected = header.size() + header.data_size();
+    if data.len() < expected {
+        return Err(TlError::Truncated { expected, actual: data.len() });
+    }
+
+    let tiles = &data[header.size()..expected];
+    let sheet = TileSheet {
+        pixels: header.format.decode(tiles, header.tiles_x(), header.tiles_y(), header.mode),
+        width: header.tiles_x() * 8,
+        height: header.tiles_y() * header.mode as u32,
+        mode: header.mode
+    };
+    Ok((sheet, header.format))
+}
+
+/// parses a complete .tl file, dropping the tile format
+pub fn from_tl(data: &[u8]) -> Result<TileSheet, TlError>
+{
+    read_tl(data).map(|(sheet, _)| sheet)
+}
+
+/// serializes the sheet as .tl file with `format` tiles, the format stores at most 255x255 8 pixel blocks.
+/// GB tiles are written as version 1 so older readers still load them
+pub fn to_tl_as(sheet: &TileSheet, format: TileFormat) -> Result<Vec<u8>, TlError>
+{
+    let w = sheet.width / 8;
+    let h = sheet.height / 8;
+    if w == 0 || h == 0 || w > 255 || h > 255 {
+        return Err(TlError::BadDimensions { width: sheet.width, height: sheet.height });
+    }
+
+    let tiles = sheet.export_as(format);
+    let mut data = Vec::with_capacity(TL_HEADER_SIZE + tiles.len());
+    data.extend_from_slice(TL_MAGIC);
+    data.push(if format == TileFormat::Gb { 1 } else { TL_VERSION });
+    data.push(sheet.mode as u8);
+    data.push(w as u8);
+    data.push(h as u8);
+    if format != TileFormat::Gb {
+        data.push(format as u8);
+    }
+    data.extend_from_slice(&tiles);
+    Ok(data)
+}
+
+/// serializes the sheet as .tl file with GB 2bpp tiles
+pub fn to_tl(sheet: &TileSheet) -> Result<Vec<u8>, TlError>
+{
+    to_tl_as(sheet, TileFormat::Gb)
+}
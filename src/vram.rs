@@ -0,0 +1,162 @@
+use core::fmt;
+use alloc::vec::Vec;
+use crate::codec::TileMode;
+use crate::format::TileFormat;
+use crate::sheet::TileSheet;
+use crate::tilemap::{MapCell, Tilemap};
+
+/// one bank of $8000-$9FFF, CGB dumps have two
+pub const VRAM_BANK_SIZE: usize = 0x2000;
+/// tile blocks $8000, $8800 and $9000 hold 128 tiles each
+pub const TILE_BLOCK_SIZE: usize = 0x800;
+/// tiles per bank, CGB bank 1 tiles follow bank 0 in the imported tileset
+pub const BANK_TILES: u16 = 384;
+
+const MAP_OFFSETS: [usize; 2] = [0x1800, 0x1c00];
+/// LCDC bit 4, BG tile data at $8000 instead of $8800
+const LCDC_TILE_DATA: u8 = 0x10;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum VramError
+{
+    /// raw dumps are 8KB (DMG) or 16KB (CGB)
+    BadSize(usize),
+    BadSaveState
+}
+
+impl fmt::Display for VramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VramError::BadSize(size) => write!(f, "VRAM dumps are 8192 or 16384 bytes, got {}", size),
+            VramError::BadSaveState => write!(f, "not a BESS save state or no VRAM in it")
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VramError {}
+
+/// How BG map entries index the tile blocks, selected by LCDC bit 4
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TileAddressing
+{
+    /// indices 0-255 from $8000
+    Unsigned,
+    /// indices -128-127 relative to $9000
+    Signed
+}
+
+impl TileAddressing
+{
+    pub fn from_lcdc(lcdc: u8) -> TileAddressing
+    {
+        if lcdc & LCDC_TILE_DATA != 0 { TileAddressing::Unsigned } else { TileAddressing::Signed }
+    }
+
+    /// tileset index of a BG map entry
+    pub fn tile(self, index: u8) -> u16
+    {
+        match self {
+            TileAddressing::Unsigned => index as u16,
+            TileAddressing::Signed if index < 128 => 256 + index as u16,
+            TileAddressing::Signed => index as u16
+        }
+    }
+}
+
+/// Tile data and both BG maps of a VRAM dump
+#[derive(Clone, PartialEq, Debug)]
+pub struct Vram
+{
+    /// 16 tiles wide, 8 rows per block $8000/$8800/$9000, CGB bank 1 follows bank 0
+    pub tileset: TileSheet,
+    /// maps at $9800 and $9C00, CGB dumps include the bank 1 attributes
+    pub maps: [Tilemap; 2],
+    /// LCDC at the time of a save state, unknown for raw dumps
+    pub lcdc: Option<u8>
+}
+
+/// imports a raw VRAM dump, 8KB for DMG or 16KB for CGB with bank 0 followed by bank 1
+pub fn read_vram(data: &[u8], addressing: TileAddressing) -> Result<Vram, VramError>
+{
+    if data.len() != VRAM_BANK_SIZE && data.len() != 2 * VRAM_BANK_SIZE {
+        return Err(VramError::BadSize(data.len()));
+    }
+
+    let tiles: Vec<u8> = data.chunks_exact(VRAM_BANK_SIZE).flat_map(|bank| &bank[..3 * TILE_BLOCK_SIZE]).copied().collect();
+    let tileset = TileSheet::from_tiles_as(TileFormat::Gb, &tiles, 16, TileMode::Y8);
+
+    let bank0 = &data[..VRAM_BANK_SIZE];
+    let attributes = data.chunks_exact(VRAM_BANK_SIZE).nth(1);
+    let maps = MAP_OFFSETS.map(|offset| {
+        let mut map = Tilemap::new(32, 32);
+        for (i, cell) in map.cells.iter_mut().enumerate() {
+            let attr = attributes.map_or(0, |bank1| bank1[offset + i]);
+            *cell = MapCell {
+                tile: addressing.tile(bank0[offset + i]) + if attr & 0x08 != 0 { BANK_TILES } else { 0 },
+                flip_x: attr & 0x20 != 0,
+                flip_y: attr & 0x40 != 0,
+                palette: attr & 0x07,
+                priority: attr & 0x80 != 0
+            };
+        }
+        map
+    });
+
+    Ok(Vram { tileset, maps, lcdc: None })
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<usize>
+{
+    let bytes = data.get(at..at.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+}
+
+/// VRAM and LCDC from the CORE block of a BESS save state
+fn find_bess_vram(data: &[u8]) -> Option<(&[u8], u8)>
+{
+    let footer = data.len().checked_sub(8)?;
+    if &data[footer + 4..] != b"BESS" {
+        return None;
+    }
+
+    let mut pos = read_u32(data, footer)?;
+    loop {
+        // offsets and lengths come from the file, they may overflow usize on 32 bit targets
+        let id = data.get(pos..pos.checked_add(4)?)?;
+        let len = read_u32(data, pos.checked_add(4)?)?;
+        let start = pos.checked_add(8)?;
+        let end = start.checked_add(len)?;
+        let block = data.get(start..end)?;
+        match id {
+            b"CORE" => {
+                // registers FF00-FF7F start at 0x18, VRAM size and offset at 0xA0
+                let lcdc = *block.get(0x18 + 0x40)?;
+                let size = read_u32(block, 0xa0)?;
+                let offset = read_u32(block, 0xa4)?;
+                return Some((data.get(offset..offset.checked_add(size)?)?, lcdc));
+            }
+            b"END " => return None,
+            _ => pos = end
+        }
+    }
+}
+
+/// imports VRAM from a BESS save state as written by SameBoy and BGB, the saved LCDC selects the tile addressing
+pub fn read_save_state(data: &[u8]) -> Result<Vram, VramError>
+{
+    let (vram, lcdc) = find_bess_vram(data).ok_or(VramError::BadSaveState)?;
+    let mut imported = read_vram(vram, TileAddressing::from_lcdc(lcdc))?;
+    imported.lcdc = Some(lcdc);
+    Ok(imported)
+}
+
+/// imports a raw dump by its size, anything else is tried as save state
+pub fn import(data: &[u8], addressing: TileAddressing) -> Result<Vram, VramError>
+{
+    if data.len() == VRAM_BANK_SIZE || data.len() == 2 * VRAM_BANK_SIZE {
+        read_vram(data, addressing)
+    } else {
+        read_save_state(data)
+    }
+}
@@ -0,0 +1,16 @@
+#!/usr/bin/env bash
+# Trunk post_build hook: lists the built files in the service worker and versions its cache
+# by their hash, so the hashed wasm and js names are cached and each new build replaces the old one.
+set -eu
+
+cd "$TRUNK_STAGING_DIR"
+files=$(find . -type f ! -name sw.js | sed 's|^\./||' | LC_ALL=C sort)
+version=$(for file in $files; do sha256sum "$file"; done | sha256sum | cut -c1-16)
+list=$(for file in $files; do printf "  './%s',\\\\n" "$file"; done)
+
+awk -v version="$version" -v files="$list" '
+  /\/\/ @FILES@/ { printf "%s", files; next }
+  { gsub(/@VERSION@/, version); print }
+' sw.js > sw.js.tmp
+mv sw.js.tmp sw.js
+echo "sw.js: cache version $version"
@@ -0,0 +1,61 @@
+use rzrtiles::animation::{AnimFrame, Animation, GB_FPS};
+
+fn walk() -> Animation
+{
+    Animation {
+        name: "walk cycle".into(),
+        frames: vec![
+            AnimFrame { first_tile: 0, tile_count: 4, columns: 2, duration: 8 },
+            AnimFrame { first_tile: 300, tile_count: 5, columns: 2, duration: 4 }
+        ]
+    }
+}
+
+#[test]
+fn frames_loop()
+{
+    let anim = walk();
+    assert_eq!(anim.total_duration(), 12);
+    assert_eq!(anim.frame_at(0), Some(0));
+    assert_eq!(anim.frame_at(7), Some(0));
+    assert_eq!(anim.frame_at(8), Some(1));
+    assert_eq!(anim.frame_at(11), Some(1));
+    // wraps around after one loop
+    assert_eq!(anim.frame_at(12), Some(0));
+    assert_eq!(anim.frame_at(12 * 1000 + 9), Some(1));
+
+    assert_eq!(anim.frame_at_time(0.0), Some(0));
+    assert_eq!(anim.frame_at_time(9.5 / GB_FPS), Some(1));
+    assert_eq!(anim.frame_at_time(12.5 / GB_FPS), Some(0));
+
+    let still = Animation { name: "still".into(), frames: vec![AnimFrame { duration: 0, ..AnimFrame::default() }] };
+    assert_eq!(still.frame_at(5), None);
+}
+
+#[test]
+fn frame_layout()
+{
+    let anim = walk();
+    assert_eq!(anim.frames[0].rows(), 2);
+    assert_eq!(anim.frames[1].rows(), 3);
+    assert_eq!(AnimFrame { columns: 0, tile_count: 3, ..AnimFrame::default() }.rows(), 3);
+    assert_eq!(AnimFrame { tile_count: u32::MAX, columns: 2, ..AnimFrame::default() }.rows(), u32::MAX / 2 + 1);
+
+    assert!(anim.frames[1].contains(300) && anim.frames[1].contains(304));
+    assert!(!anim.frames[1].contains(299) && !anim.frames[1].contains(305));
+    // deserialized state may hold any range
+    let huge = AnimFrame { first_tile: u32::MAX - 1, tile_count: u32::MAX, ..AnimFrame::default() };
+    assert!(huge.contains(u32::MAX) && !huge.contains(0));
+}
+
+#[test]
+fn frame_table_text()
+{
+    assert_eq!(walk().frame_table(), "\
+; 2 frames, 12 GB frames per loop
+walk_cycle:
+    dw 2
+    dw 0, 4, 2, 8
+    dw 300, 5, 2, 4
+");
+}
@@ -0,0 +1,196 @@
+use rzrtiles::aseprite::{self, AseError};
+use rzrtiles::codec::TileMode;
+
+fn chunk(kind: u16, data: &[u8]) -> Vec<u8>
+{
+    let mut out = ((data.len() + 6) as u32).to_le_bytes().to_vec();
+    out.extend_from_slice(&kind.to_le_bytes());
+    out.extend_from_slice(data);
+    out
+}
+
+fn layer(flags: u16, kind: u16, level: u16, name: &str) -> Vec<u8>
+{
+    let mut data = Vec::new();
+    for word in [flags, kind, level, 0, 0, 0] {
+        data.extend_from_slice(&word.to_le_bytes());
+    }
+    data.extend_from_slice(&[255, 0, 0, 0]);
+    data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    data.extend_from_slice(name.as_bytes());
+    chunk(0x2004, &data)
+}
+
+/// cel header up to the cel type specific data
+fn cel_header(layer: u16, x: i16, y: i16, kind: u16) -> Vec<u8>
+{
+    let mut data = Vec::new();
+    data.extend_from_slice(&layer.to_le_bytes());
+    data.extend_from_slice(&x.to_le_bytes());
+    data.extend_from_slice(&y.to_le_bytes());
+    data.push(255);
+    data.extend_from_slice(&kind.to_le_bytes());
+    data.extend_from_slice(&[0; 7]);
+    data
+}
+
+fn image_cel(layer: u16, x: i16, y: i16, w: u16, h: u16, pixels: &[u8], compressed: bool) -> Vec<u8>
+{
+    let mut data = cel_header(layer, x, y, if compressed { 2 } else { 0 });
+    data.extend_from_slice(&w.to_le_bytes());
+    data.extend_from_slice(&h.to_le_bytes());
+    if compressed {
+        data.extend_from_slice(&miniz_oxide::deflate::compress_to_vec_zlib(pixels, 6));
+    } else {
+        data.extend_from_slice(pixels);
+    }
+    chunk(0x2005, &data)
+}
+
+fn linked_cel(layer: u16, frame: u16) -> Vec<u8>
+{
+    let mut data = cel_header(layer, 0, 0, 1);
+    data.extend_from_slice(&frame.to_le_bytes());
+    chunk(0x2005, &data)
+}
+
+fn palette(colors: &[[u8; 4]]) -> Vec<u8>
+{
+    let mut data = Vec::new();
+    for value in [colors.len() as u32, 0, colors.len() as u32 - 1] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(&[0; 8]);
+    for color in colors {
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(color);
+    }
+    chunk(0x2019, &data)
+}
+
+fn tags(tags: &[(u16, u16, &str)]) -> Vec<u8>
+{
+    let mut data = (tags.len() as u16).to_le_bytes().to_vec();
+    data.extend_from_slice(&[0; 8]);
+    for (from, to, name) in tags {
+        data.extend_from_slice(&from.to_le_bytes());
+        data.extend_from_slice(&to.to_le_bytes());
+        data.extend_from_slice(&[0; 13]);
+        data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        data.extend_from_slice(name.as_bytes());
+    }
+    chunk(0x2018, &data)
+}
+
+fn file(width: u16, height: u16, depth: u16, frames: &[(u16, Vec<Vec<u8>>)]) -> Vec<u8>
+{
+    let mut header = vec![0u8; 128];
+    header[4..6].copy_from_slice(&0xa5e0u16.to_le_bytes());
+    header[6..8].copy_from_slice(&(frames.len() as u16).to_le_bytes());
+    header[8..10].copy_from_slice(&width.to_le_bytes());
+    header[10..12].copy_from_slice(&height.to_le_bytes());
+    header[12..14].copy_from_slice(&depth.to_le_bytes());
+    header[28] = 0; // transparent index
+
+    let mut out = header;
+    for (duration, chunks) in frames {
+        let body: Vec<u8> = chunks.concat();
+        out.extend_from_slice(&((body.len() + 16) as u32).to_le_bytes());
+        out.extend_from_slice(&0xf1fau16.to_le_bytes());
+        out.extend_from_slice(&(chunks.len() as u16).to_le_bytes());
+        out.extend_from_slice(&duration.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+        out.extend_from_slice(&body);
+    }
+    let size = out.len() as u32;
+    out[..4].copy_from_slice(&size.to_le_bytes());
+    out
+}
+
+/// 10x9 sprite: a background layer of 1s, a layer painting a 2x2 block of 3s with a transparent corner,
+/// a hidden layer and a hidden group whose visible child must stay hidden too
+fn sprite() -> Vec<u8>
+{
+    let colors = [[255, 255, 255, 0], [170, 170, 170, 255], [85, 85, 85, 255], [0, 0, 0, 255]];
+    let first = vec![
+        palette(&colors),
+        layer(1 | 8, 0, 0, "Background"),
+        layer(1, 0, 0, "Sprite"),
+        layer(0, 0, 0, "Hidden"),
+        layer(0, 1, 0, "Group"),
+        layer(1, 0, 1, "Child"),
+        tags(&[(0, 1, "walk"), (2, 2, "idle")]),
+        image_cel(0, 0, 0, 10, 9, &[1; 90], false),
+        image_cel(1, 8, 7, 2, 2, &[3, 3, 0, 3], true),
+        image_cel(2, 0, 0, 1, 1, &[2], false),
+        image_cel(4, 1, 0, 1, 1, &[2], false)
+    ];
+    let second = vec![linked_cel(0, 0), image_cel(1, -1, -1, 2, 2, &[2, 2, 2, 2], false)];
+    let third = vec![image_cel(1, 0, 0, 1, 1, &[3], true)];
+    file(10, 9, 8, &[(100, first), (50, second), (1000, third)])
+}
+
+#[test]
+fn flattens_visible_layers()
+{
+    let ase = aseprite::read_aseprite(&sprite()).unwrap();
+    assert_eq!((ase.width, ase.height, ase.frames.len()), (10, 9, 3));
+    assert_eq!(ase.palette[3], [0, 0, 0, 255]);
+
+    let first = &ase.frames[0].pixels;
+    assert_eq!(first[0], 1);
+    assert_eq!(first[1], 1, "child of a hidden group");
+    assert_eq!(&first[7 * 10 + 8..7 * 10 + 10], [3, 3]);
+    assert_eq!(&first[8 * 10 + 8..8 * 10 + 10], [1, 3], "transparent sprite pixel shows the background");
+
+    // linked background, sprite cel partly off the canvas
+    let second = &ase.frames[1].pixels;
+    assert_eq!(second[0], 2);
+    assert_eq!(second[1..].iter().filter(|p| **p != 1).count(), 0);
+
+    // no background cel in the third frame, uncovered pixels stay transparent
+    let third = &ase.frames[2].pixels;
+    assert_eq!(third[0], 3);
+    assert!(third[1..].iter().all(|p| *p == 0));
+}
+
+#[test]
+fn frames_become_tiles_and_animations()
+{
+    let ase = aseprite::read_aseprite(&sprite()).unwrap();
+
+    // 10x9 pads to 2x2 tiles per frame in 8x8 mode and 2x1 in 8x16 mode
+    let sheet = ase.to_sheet(TileMode::Y8);
+    assert_eq!((sheet.width, sheet.height), (16, 48));
+    assert_eq!(sheet.get(9, 8), 3);
+    assert_eq!(sheet.get(10, 0), 0);
+    assert_eq!(sheet.get(0, 16), 2);
+    assert_eq!(ase.to_sheet(TileMode::Y16).height, 48);
+
+    let anims = ase.animations("player", TileMode::Y8);
+    let names: Vec<&str> = anims.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, ["walk", "idle"]);
+    assert_eq!(anims[0].frames.len(), 2);
+
+    let idle = anims[1].frames[0];
+    assert_eq!((idle.first_tile, idle.tile_count, idle.columns), (8, 4, 2));
+    assert_eq!(idle.duration, 60);
+    assert_eq!(anims[0].frames[1].duration, 3);
+}
+
+#[test]
+fn rejects_other_files()
+{
+    assert_eq!(aseprite::read_aseprite(&file(8, 8, 32, &[])), Err(AseError::ColorDepth(32)));
+    assert_eq!(aseprite::read_aseprite(&[0; 64]), Err(AseError::Truncated));
+
+    let mut data = sprite();
+    data[5] = 0;
+    assert_eq!(aseprite::read_aseprite(&data), Err(AseError::BadMagic));
+
+    let data = sprite();
+    for len in [130, 200, data.len() - 1] {
+        assert!(aseprite::read_aseprite(&data[..len]).is_err(), "truncated to {}", len);
+    }
+}
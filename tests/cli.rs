@@ -0,0 +1,57 @@
+use std::path::Path;
+use std::process::Command;
+
+fn rzrtiles(dir: &Path, args: &[&str]) -> serde_json::Value
+{
+    let output = Command::new(env!("CARGO_BIN_EXE_rzrtiles"))
+        .current_dir(dir)
+        .args(args)
+        .arg("--json")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stdout));
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn partial_last_row_round_trips()
+{
+    let dir = std::env::temp_dir().join(format!("rzrtiles_cli_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // 24 tiles at 16 per row, tiles 20..24 repeat tiles 0..4
+    let data: Vec<u8> = (0..24u8).flat_map(|t| [t % 20 + 1; 16]).collect();
+    std::fs::write(dir.join("in.2bpp"), &data).unwrap();
+
+    let converted = rzrtiles(&dir, &["convert", "in.2bpp", "out.2bpp"]);
+    assert_eq!(converted["bytes"], 24 * 16);
+    assert_eq!(converted["sheet"]["tiles"], 24);
+    assert_eq!(std::fs::read(dir.join("out.2bpp")).unwrap(), data);
+
+    let deduped = rzrtiles(&dir, &["dedupe", "in.2bpp", "unique.2bpp", "--map", "map.bin"]);
+    assert_eq!((&deduped["tiles"], &deduped["unique"]), (&24.into(), &20.into()));
+    assert_eq!(std::fs::read(dir.join("unique.2bpp")).unwrap(), data[..20 * 16]);
+    let map: Vec<u8> = (0..24).map(|t| t % 20).collect();
+    assert_eq!(std::fs::read(dir.join("map.bin")).unwrap(), map);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn builds_a_project_from_files()
+{
+    let dir = std::env::temp_dir().join(format!("rzrtiles_cli_build_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("art")).unwrap();
+    let data: Vec<u8> = (0..4u8).flat_map(|t| [t; 16]).collect();
+    std::fs::write(dir.join("art/font.2bpp"), &data).unwrap();
+    std::fs::write(dir.join("game.json"), r#"{
+        "documents": [{ "name": "font", "path": "art/font.2bpp" }],
+        "exports": [{ "document": "font", "path": "build/font.2bpp", "format": "Raw" }]
+    }"#).unwrap();
+
+    let built = rzrtiles(&dir, &["build", "game.json"]);
+    assert_eq!(built["outputs"]["build/font.2bpp"], 4 * 16);
+    assert_eq!(std::fs::read(dir.join("build/font.2bpp")).unwrap(), data);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,192 @@
+use rzrtiles::codec::{self, scalar, SizeError, TileMode};
+use rzrtiles::format::TileFormat;
+
+#[test]
+fn decode_row_matches_scalar()
+{
+    for left in 0..=255u8 {
+        for right in 0..=255u8 {
+            let mut fast = [0u8; 8];
+            let mut reference = [0u8; 8];
+            codec::decode_row(left, right, &mut fast);
+            scalar::decode_row(left, right, &mut reference);
+            assert_eq!(fast, reference, "left {:02X} right {:02X}", left, right);
+        }
+    }
+}
+
+#[test]
+fn encode_row_matches_scalar()
+{
+    // every row of 2 bit indices
+    for bits in 0..=0xffffu32 {
+        let row: Vec<u8> = (0..8).map(|i| (bits >> (2 * i)) as u8 & 0b11).collect();
+        assert_eq!(codec::encode_row(&row), scalar::encode_row(&row), "row {:?}", row);
+    }
+
+    // bits above the two planes are ignored by both
+    for seed in 0..4096u32 {
+        let row: Vec<u8> = (0..8).map(|i| (seed.wrapping_mul(2654435761).rotate_left(i * 4)) as u8).collect();
+        assert_eq!(codec::encode_row(&row), scalar::encode_row(&row), "row {:?}", row);
+    }
+}
+
+#[test]
+fn sheet_roundtrip()
+{
+    for mode in [TileMode::Y8, TileMode::Y16] {
+        let (w, h) = (4u32, 3u32);
+        let tiles: Vec<u8> = (0..w * h * mode as u32 * 2).map(|i| (i * 37 + 11) as u8).collect();
+        let pixels = codec::gb_tiles_to_pixels(&tiles, w, h, mode);
+        assert_eq!(codec::pixels_to_gb_tiles(&pixels, w * 8, h * mode as u32, mode), tiles);
+    }
+}
+
+#[test]
+fn slice_and_tile_functions_match_sheet_functions()
+{
+    for mode in [TileMode::Y8, TileMode::Y16] {
+        let (w, h) = (3u32, 2u32);
+        let ystep = mode as usize;
+        let width = w as usize * 8;
+        let bytes_per_tile = mode.bytes_per_tile();
+        let tiles: Vec<u8> = (0..w * h * mode as u32 * 2).map(|i| (i * 53 + 7) as u8).collect();
+        let pixels = codec::gb_tiles_to_pixels(&tiles, w, h, mode);
+
+        // every output byte is written, whatever the buffer held before
+        let mut decoded = vec![0xaa; pixels.len()];
+        codec::decode_into(&tiles, w, h, mode, &mut decoded);
+        assert_eq!(decoded, pixels, "decode_into {:?}", mode);
+
+        let mut encoded = vec![0xaa; tiles.len()];
+        codec::encode_into(&pixels, w * 8, h * mode as u32, mode, &mut encoded);
+        assert_eq!(encoded, tiles, "encode_into {:?}", mode);
+
+        for t in 0..(w * h) as usize {
+            let origin = (t / w as usize) * ystep * width + (t % w as usize) * 8;
+            let data = &tiles[t * bytes_per_tile..(t + 1) * bytes_per_tile];
+
+            let mut tile = vec![0xaa; bytes_per_tile];
+            codec::encode_tile(&pixels[origin..], width, &mut tile);
+            assert_eq!(tile, data, "encode_tile {:?} tile {}", mode, t);
+
+            let mut out = vec![0xaa; 8 * ystep];
+            codec::decode_tile(data, &mut out, 8);
+            for y in 0..ystep {
+                assert_eq!(out[y * 8..y * 8 + 8], pixels[origin + y * width..origin + y * width + 8], "decode_tile {:?} tile {} row {}", mode, t, y);
+            }
+        }
+    }
+}
+
+// example tile from the NESdev wiki PPU pattern table article
+const NES_TILE: [u8; 16] = [
+    0x41, 0xC2, 0x44, 0x48, 0x10, 0x20, 0x40, 0x80,
+    0x01, 0x02, 0x04, 0x08, 0x16, 0x21, 0x42, 0x87
+];
+const NES_PIXELS: &str = "\
+.1.....3\
+11....3.\
+.1...3..\
+.1..3...\
+...3.22.\
+..3....2\
+.3....2.\
+3....222";
+
+#[test]
+fn nes_tile()
+{
+    let expected: Vec<u8> = NES_PIXELS.bytes().map(|c| if c == b'.' { 0 } else { c - b'0' }).collect();
+    assert_eq!(TileFormat::Nes.decode(&NES_TILE, 1, 1, TileMode::Y8), expected);
+    assert_eq!(TileFormat::Nes.encode(&expected, 8, 8, TileMode::Y8), NES_TILE);
+}
+
+#[test]
+fn nes_8x16_is_top_then_bottom_tile()
+{
+    let top: Vec<u8> = (0..64).map(|i| (i % 3) as u8).collect();
+    let bottom: Vec<u8> = (0..64).map(|i| (i % 4) as u8).collect();
+    let sprite = [top.clone(), bottom.clone()].concat();
+
+    let chr = TileFormat::Nes.encode(&sprite, 8, 16, TileMode::Y16);
+    let mut pair = TileFormat::Nes.encode(&top, 8, 8, TileMode::Y8);
+    pair.extend(TileFormat::Nes.encode(&bottom, 8, 8, TileMode::Y8));
+    assert_eq!(chr, pair);
+    assert_eq!(TileFormat::Nes.decode(&chr, 1, 1, TileMode::Y16), sprite);
+}
+
+#[test]
+fn all_formats_roundtrip()
+{
+    for format in TileFormat::ALL {
+        for mode in [TileMode::Y8, TileMode::Y16] {
+            let (w, h) = (3u32, 2u32);
+            let pixels: Vec<u8> = (0..w * 8 * h * mode as u32).map(|i| (i * 7 + i / 5) as u8 % format.colors() as u8).collect();
+            let tiles = format.encode(&pixels, w * 8, h * mode as u32, mode);
+            assert_eq!(tiles.len(), (w * h) as usize * format.bytes_per_tile(mode), "{:?}", format);
+            assert_eq!(format.decode(&tiles, w, h, mode), pixels, "{:?} {:?}", format, mode);
+        }
+    }
+}
+
+#[test]
+fn snes_low_planes_are_gb_tile()
+{
+    let pixels: Vec<u8> = (0..64).map(|i| (i * 5 % 16) as u8).collect();
+    let low: Vec<u8> = pixels.iter().map(|p| p & 0b11).collect();
+    let high: Vec<u8> = pixels.iter().map(|p| p >> 2).collect();
+
+    let snes = TileFormat::Snes.encode(&pixels, 8, 8, TileMode::Y8);
+    assert_eq!(snes[..16], TileFormat::Gb.encode(&low, 8, 8, TileMode::Y8)[..]);
+    assert_eq!(snes[16..], TileFormat::Gb.encode(&high, 8, 8, TileMode::Y8)[..]);
+}
+
+#[test]
+fn sms_row_has_four_planes()
+{
+    // first row uses colors 1, 2, 4 and 8 in its first four pixels
+    let mut pixels = vec![0u8; 64];
+    pixels[..4].copy_from_slice(&[1, 2, 4, 8]);
+
+    let sms = TileFormat::Sms.encode(&pixels, 8, 8, TileMode::Y8);
+    assert_eq!(sms[..4], [0x80, 0x40, 0x20, 0x10]);
+    assert!(sms[4..].iter().all(|b| *b == 0));
+}
+
+#[test]
+fn gba_left_pixel_is_low_nibble()
+{
+    let mut pixels = vec![0u8; 64];
+    pixels[..2].copy_from_slice(&[0x3, 0xC]);
+    pixels[63] = 0x5;
+
+    let gba = TileFormat::Gba.encode(&pixels, 8, 8, TileMode::Y8);
+    assert_eq!(gba.len(), 32);
+    assert_eq!(gba[0], 0xC3);
+    assert_eq!(gba[31], 0x50);
+}
+
+#[test]
+fn checked_functions_report_size_mismatches()
+{
+    let tiles = [0u8; 48];
+    let mut pixels = [0u8; 192];
+    assert_eq!(codec::try_decode_into(&tiles, 3, 1, TileMode::Y8, &mut pixels), Ok(()));
+    assert_eq!(codec::try_decode_into(&tiles, 2, 1, TileMode::Y8, &mut pixels), Err(SizeError::Input { expected: 32, actual: 48 }));
+    assert_eq!(codec::try_decode_into(&tiles, 3, 1, TileMode::Y8, &mut pixels[1..]), Err(SizeError::Output { expected: 192, actual: 191 }));
+    assert!(codec::try_decode_into(&tiles, u32::MAX, u32::MAX, TileMode::Y16, &mut pixels).is_err());
+
+    let mut out = [0u8; 48];
+    assert_eq!(codec::try_encode_into(&pixels, 24, 8, TileMode::Y8, &mut out), Ok(()));
+    assert_eq!(codec::try_encode_into(&pixels, 12, 16, TileMode::Y8, &mut out), Err(SizeError::Dimensions { width: 12, height: 16 }));
+    assert_eq!(codec::try_encode_into(&pixels, 24, 8, TileMode::Y16, &mut out), Err(SizeError::Dimensions { width: 24, height: 8 }));
+    assert_eq!(TileFormat::Snes.try_encode_into(&pixels, 24, 8, TileMode::Y8, &mut out), Err(SizeError::Output { expected: 96, actual: 48 }));
+}
+
+#[test]
+#[should_panic(expected = "input holds 48 bytes, expected 32")]
+fn unchecked_decode_panics_on_size_mismatch()
+{
+    codec::decode_into(&[0; 48], 2, 1, TileMode::Y8, &mut [0; 128]);
+}
@@ -0,0 +1,93 @@
+use rzrtiles::codec::TileMode;
+use rzrtiles::compress::{self, CompressError, Compression};
+use rzrtiles::convert::{self, ExportFormat, ExportOptions};
+use rzrtiles::sheet::TileSheet;
+
+fn inputs() -> Vec<Vec<u8>>
+{
+    let mut noise = Vec::new();
+    let mut x = 12345u32;
+    for _ in 0..5000 {
+        x = x.wrapping_mul(1103515245).wrapping_add(12345);
+        noise.push((x >> 16) as u8);
+    }
+
+    // tiles with repeated rows, empty space and a long run
+    let mut sheet = TileSheet::new(64, 48, TileMode::Y8);
+    for y in 0..24 {
+        for x in 0..64 {
+            sheet.set(x, y, ((x / 3 + y / 5) % 4) as u8);
+        }
+    }
+
+    vec![
+        Vec::new(),
+        vec![7],
+        vec![1, 2],
+        vec![0; 1000],
+        (0..=255).collect(),
+        (0..300).map(|i| (i % 3) as u8).collect(),
+        [vec![5; 129], vec![5; 130], vec![6]].concat(),
+        noise,
+        sheet.export(),
+        sheet.export()[..13].to_vec()
+    ]
+}
+
+#[test]
+fn roundtrip()
+{
+    for compression in Compression::ALL {
+        for data in inputs() {
+            let packed = compression.compress(&data);
+            assert_eq!(compression.decompress(&packed).unwrap(), data, "{} of {} bytes", compression.name(), data.len());
+        }
+    }
+}
+
+#[test]
+fn compresses_tiles()
+{
+    let tiles = inputs()[8].clone();
+    for compression in [Compression::Rle, Compression::Pb16, Compression::Lz77] {
+        let packed = compression.compress(&tiles);
+        assert!(packed.len() < tiles.len(), "{} {} -> {}", compression.name(), tiles.len(), packed.len());
+    }
+}
+
+#[test]
+fn known_streams()
+{
+    // 3 literals, then 130 - 0x7e = 4 repeats of 9
+    assert_eq!(compress::rle_compress(&[1, 2, 3, 9, 9, 9, 9]), [2, 1, 2, 3, 0x82, 9]);
+
+    // a tile row of $FF $00 repeated, every byte after the first row repeats the one two back
+    assert_eq!(compress::pb16_compress(&[0xff, 0x00, 0xff, 0x00, 0xff, 0x00, 0xff, 0x00]), [0b0111_1111, 0xff]);
+
+    // one literal, then a 17 byte match at distance 1
+    assert_eq!(compress::lz77_compress(&[4; 18]), [0b01, 4, 0xe0, 0x00]);
+}
+
+#[test]
+fn corrupt_streams()
+{
+    assert_eq!(compress::rle_decompress(&[5, 1, 2]), Err(CompressError::Truncated));
+    assert_eq!(compress::rle_decompress(&[0x80]), Err(CompressError::Truncated));
+    assert_eq!(compress::pb16_decompress(&[0x00]), Err(CompressError::Truncated));
+    assert_eq!(compress::lz77_decompress(&[0x00, 0x00]), Err(CompressError::Truncated));
+    assert_eq!(compress::lz77_decompress(&[0x00, 0x00, 0x00]), Err(CompressError::BadOffset { at: 0, offset: 1 }));
+}
+
+#[test]
+fn export_options()
+{
+    let sheet = TileSheet::new(16, 16, TileMode::Y8);
+    let raw = convert::encode(&sheet, ExportFormat::Raw).unwrap();
+
+    let options = ExportOptions { compression: Compression::Pb16, ..Default::default() };
+    let packed = convert::compress(raw.clone(), &options).unwrap();
+    assert_eq!(Compression::Pb16.decompress(&packed).unwrap(), raw);
+
+    let png = ExportOptions { format: ExportFormat::Png, ..options };
+    assert!(convert::compress(raw, &png).is_err());
+}
@@ -0,0 +1,133 @@
+use rzrtiles::codec::TileMode;
+use rzrtiles::compress::Compression;
+use rzrtiles::convert::{self, Asset, ExportFormat, ExportOptions, InputFormat};
+use rzrtiles::dedupe::{dedupe, dedupe_as, dedupe_first};
+use rzrtiles::format::{TileFormat, CHR_BANK_SIZE};
+use rzrtiles::sheet::{TileSheet, GB_SHADES};
+use rzrtiles::tl;
+
+/// two tiles that only differ in the high bitplanes, index 15 against index 3
+fn sheet_4bpp() -> TileSheet
+{
+    let mut sheet = TileSheet::new(16, 8, TileMode::Y8);
+    for y in 0..8 {
+        for x in 0..8 {
+            sheet.set(x, y, 3);
+            sheet.set(x + 8, y, if x == y { 15 } else { 3 });
+        }
+    }
+    sheet
+}
+
+#[test]
+fn tl_keeps_its_tile_format()
+{
+    let sheet = sheet_4bpp();
+    let data = tl::to_tl_as(&sheet, TileFormat::Snes).unwrap();
+    let asset = convert::load_bytes(&data, InputFormat::Tl, &ExportOptions::default()).unwrap();
+    assert_eq!((&asset.sheet, asset.format, asset.tiles), (&sheet, TileFormat::Snes, 2));
+    let (loaded, format) = (&asset.sheet, asset.format);
+
+    let saved = convert::encode_as(loaded, format, ExportFormat::Tl).unwrap();
+    assert_eq!(tl::read_tl(&saved).unwrap(), (sheet.clone(), TileFormat::Snes));
+
+    // 2bpp outputs fail instead of dropping bitplanes
+    assert!(asset.encode(ExportFormat::Raw).is_err());
+    assert!(convert::encode_as(loaded, TileFormat::Gb, ExportFormat::Tl).is_err());
+    assert!(convert::encode(loaded, ExportFormat::Png).is_err());
+}
+
+#[test]
+fn dedupes_in_the_sheet_format()
+{
+    let sheet = sheet_4bpp();
+    assert_eq!(dedupe(&sheet).map, [0, 0], "2bpp drops the high bits");
+    let deduped = dedupe_as(&sheet, TileFormat::Snes);
+    assert_eq!((deduped.map.as_slice(), deduped.unique_count(TileFormat::Snes.bytes_per_tile(TileMode::Y8))), ([0, 1].as_slice(), 2));
+
+    let options = ExportOptions { dedupe: true, ..Default::default() };
+    let asset = Asset::new(sheet.clone(), TileFormat::Snes);
+    assert_eq!(convert::prepare(asset.clone(), &options), asset);
+}
+
+#[test]
+fn raw_output_stops_at_the_source_tiles()
+{
+    // 24 tiles fill one and a half rows of 16, the last 4 repeat earlier ones and none matches the empty padding
+    let data: Vec<u8> = (0..24u8).flat_map(|t| [t % 20 + 1; 16]).collect();
+    let asset = convert::load_bytes(&data, InputFormat::Raw, &ExportOptions::default()).unwrap();
+    assert_eq!((asset.sheet.tile_count(), asset.tiles), (32, 24));
+    assert_eq!(asset.encode(ExportFormat::Raw).unwrap(), data);
+
+    let options = ExportOptions { dedupe: true, ..Default::default() };
+    let deduped = convert::prepare(asset.clone(), &options);
+    assert_eq!(deduped.tiles, 20);
+    assert_eq!(deduped.encode(ExportFormat::Raw).unwrap(), data[..20 * 16]);
+    assert_eq!(dedupe_first(&asset.sheet, asset.format, asset.tiles).map.len(), 24);
+}
+
+/// 2 tiles, the first filled with index 1 and the second with a diagonal of index 3
+fn sheet_2bpp() -> TileSheet
+{
+    let mut sheet = TileSheet::new(16, 8, TileMode::Y8);
+    for y in 0..8 {
+        for x in 0..8 {
+            sheet.set(x, y, 1);
+        }
+        sheet.set(8 + y, y, 3);
+    }
+    sheet
+}
+
+#[test]
+fn encodes_every_export_format()
+{
+    let sheet = sheet_2bpp();
+
+    let raw = convert::encode(&sheet, ExportFormat::Raw).unwrap();
+    assert_eq!(raw, sheet.export());
+    assert_eq!(&raw[..2], [0xff, 0x00]);
+
+    let chr = convert::encode(&sheet, ExportFormat::Chr).unwrap();
+    assert_eq!(chr.len(), CHR_BANK_SIZE);
+    assert_eq!(chr[..32], sheet.export_as(TileFormat::Nes)[..]);
+    assert!(chr[32..].iter().all(|b| *b == 0));
+
+    let tl_data = convert::encode(&sheet, ExportFormat::Tl).unwrap();
+    assert_eq!(tl::read_tl(&tl_data).unwrap(), (sheet.clone(), TileFormat::Gb));
+
+    let png = convert::encode(&sheet, ExportFormat::Png).unwrap();
+    let img = image::load_from_memory(&png).unwrap().to_rgba8();
+    assert_eq!(img.dimensions(), (16, 8));
+    assert_eq!(img.get_pixel(0, 0).0, GB_SHADES[1]);
+    assert_eq!(img.get_pixel(9, 1).0, GB_SHADES[3]);
+
+    let rom = convert::encode(&sheet, ExportFormat::Rom).unwrap();
+    assert_eq!(rom.len(), rzrtiles::rom::ROM_SIZE);
+
+    // .tl output keeps the tile format, the others are always 2bpp
+    let snes = convert::encode_as(&sheet, TileFormat::Snes, ExportFormat::Tl).unwrap();
+    assert_eq!(tl::read_tl(&snes).unwrap().1, TileFormat::Snes);
+    assert_eq!(convert::encode_as(&sheet, TileFormat::Snes, ExportFormat::Raw).unwrap(), raw);
+}
+
+#[test]
+fn converts_files_by_extension()
+{
+    let dir = std::env::temp_dir().join(format!("rzrtiles_convert_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let sheet = sheet_2bpp();
+    let png = dir.join("font.png");
+    std::fs::write(&png, convert::encode(&sheet, ExportFormat::Png).unwrap()).unwrap();
+
+    // light shades come back as low indices
+    let options = ExportOptions { format: ExportFormat::Raw, ..Default::default() };
+    assert_eq!(convert::convert(&png, options).unwrap(), sheet.export());
+
+    let options = ExportOptions { format: ExportFormat::Raw, dedupe: true, compression: Compression::Rle, ..Default::default() };
+    let packed = convert::convert(&png, options).unwrap();
+    assert_eq!(Compression::Rle.decompress(&packed).unwrap(), sheet.export());
+
+    assert!(convert::convert(dir.join("missing.tl"), ExportOptions::default()).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
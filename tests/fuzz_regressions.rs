@@ -0,0 +1,39 @@
+// Replays the seed corpus in fuzz/corpus and the crashing inputs in fuzz/regressions
+// through the fuzz target checks
+
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+#[path = "../fuzz/checks.rs"]
+mod checks;
+
+fn replay(target: &str, check: fn(&[u8]))
+{
+    let fuzz = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz");
+    let mut count = 0;
+    for dir in [fuzz.join("corpus").join(target), fuzz.join("regressions").join(target)] {
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let data = std::fs::read(&path).unwrap();
+            let result = panic::catch_unwind(AssertUnwindSafe(|| check(&data)));
+            assert!(result.is_ok(), "{} failed the {} checks", path.display(), target);
+            count += 1;
+        }
+    }
+    assert!(count > 0, "no inputs for {}", target);
+}
+
+#[test]
+fn tl_loader()
+{
+    replay("tl_loader", checks::tl_loader);
+}
+
+#[test]
+fn tile_decoder()
+{
+    replay("tile_decoder", checks::tile_decoder);
+}
@@ -0,0 +1,75 @@
+use rzrtiles::codec::TileMode;
+use rzrtiles::error::Error;
+use rzrtiles::gbstudio::{self, AssetKind, Violation, BACKGROUND_COLORS, MAX_BACKGROUND_TILES, SPRITE_COLORS};
+use rzrtiles::sheet::TileSheet;
+
+/// screen sized background where tile t is filled with a pattern unique to t for t < `unique`
+fn background(unique: u32) -> TileSheet
+{
+    let mut sheet = TileSheet::new(160, 144, TileMode::Y8);
+    for t in 0..unique {
+        let (x, y) = sheet.tile_origin(t).unwrap();
+        // 16 pixels of 2 bits give every tile a distinct pattern
+        for p in 0..16 {
+            sheet.set(x + p % 8, y + p / 8, ((t >> (2 * (p % 8))) & 3) as u8 ^ (p / 8) as u8);
+        }
+    }
+    sheet
+}
+
+#[test]
+fn valid_background_uses_gb_studio_colors()
+{
+    let mut sheet = background(4);
+    sheet.set(0, 0, 3);
+    assert_eq!(gbstudio::validate(&sheet, AssetKind::Background), []);
+
+    let png = gbstudio::export_png(&sheet, AssetKind::Background).unwrap();
+    let img = image::load_from_memory(&png).unwrap().to_rgba8();
+    assert_eq!((img.width(), img.height()), (160, 144));
+    assert_eq!(img.get_pixel(0, 0).0, BACKGROUND_COLORS[3]);
+    assert_eq!(img.get_pixel(159, 143).0, BACKGROUND_COLORS[0]);
+}
+
+#[test]
+fn too_many_unique_tiles()
+{
+    let limit = MAX_BACKGROUND_TILES as u32;
+    let sheet = background(limit + 1);
+    assert_eq!(gbstudio::unique_tiles(&sheet), limit as usize + 2);
+
+    // the patterned tiles are followed by empty ones, which count as one more unique tile
+    let violations = gbstudio::validate(&sheet, AssetKind::Background);
+    assert_eq!(violations, [
+        Violation::TooManyTiles { x: limit % 20, y: limit / 20, unique: MAX_BACKGROUND_TILES + 1 },
+        Violation::TooManyTiles { x: (limit + 1) % 20, y: (limit + 1) / 20, unique: MAX_BACKGROUND_TILES + 2 }
+    ]);
+    assert!(matches!(gbstudio::export_png(&sheet, AssetKind::Background), Err(Error::GbStudio(v)) if v.0 == violations));
+}
+
+#[test]
+fn size_and_color_rules()
+{
+    let mut small = TileSheet::new(16, 8, TileMode::Y8);
+    small.set(9, 3, 5);
+    assert_eq!(gbstudio::validate(&small, AssetKind::Background), [
+        Violation::TooSmall { width: 16, height: 8 },
+        Violation::Color { x: 1, y: 0, index: 5 }
+    ]);
+
+    // sprites are 8x16 tiles, index 0 is transparent and 1-3 are shades
+    assert_eq!(gbstudio::validate(&small, AssetKind::Sprite)[0], Violation::Size { width: 16, height: 8 });
+    let mut sprite = TileSheet::new(16, 32, TileMode::Y16);
+    sprite.set(3, 20, 4);
+    assert_eq!(gbstudio::validate(&sprite, AssetKind::Sprite), [Violation::Color { x: 0, y: 2, index: 4 }]);
+
+    sprite.set(3, 20, 1);
+    sprite.set(4, 20, 2);
+    sprite.set(5, 20, 3);
+    assert_eq!(gbstudio::validate(&sprite, AssetKind::Sprite), []);
+    let png = gbstudio::export_png(&sprite, AssetKind::Sprite).unwrap();
+    let img = image::load_from_memory(&png).unwrap().to_rgba8();
+    for (x, index) in [(0, 0), (3, 1), (4, 2), (5, 3)] {
+        assert_eq!(img.get_pixel(x, 20).0, SPRITE_COLORS[index]);
+    }
+}
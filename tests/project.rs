@@ -0,0 +1,104 @@
+use rzrtiles::animation::{AnimFrame, Animation};
+use rzrtiles::codec::TileMode;
+use rzrtiles::compress::Compression;
+use rzrtiles::convert::ExportFormat;
+use rzrtiles::project::{self, ExportTarget, MapDocument, Palette, Project, TileDocument};
+use rzrtiles::storage::{DocumentStorage, MemoryStorage};
+use rzrtiles::sheet::TileSheet;
+use rzrtiles::tilemap::Tilemap;
+use rzrtiles::tl;
+
+fn sheet() -> TileSheet
+{
+    let mut sheet = TileSheet::new(16, 16, TileMode::Y8);
+    for y in 0..16 {
+        for x in 0..16 {
+            sheet.set(x, y, ((x / 2 + y) % 4) as u8);
+        }
+    }
+    sheet
+}
+
+fn project() -> Project
+{
+    Project {
+        documents: vec![TileDocument {
+            name: "font".into(),
+            path: "art/font.tl".into(),
+            palette: Some("gray".into()),
+            animations: vec![Animation { name: "blink".into(), frames: vec![AnimFrame::default(), AnimFrame { first_tile: 300, ..AnimFrame::default() }] }]
+        }],
+        palettes: vec![Palette { name: "gray".into(), colors: vec![[255; 4], [170, 170, 170, 255], [85, 85, 85, 255], [0, 0, 0, 255]] }],
+        maps: vec![MapDocument { name: "title".into(), tileset: "font".into(), map: Tilemap::new(4, 2) }],
+        exports: vec![
+            ExportTarget { document: "font".into(), path: "font.2bpp".into(), format: ExportFormat::Raw, dedupe: false, compression: Compression::None },
+            ExportTarget { document: "font".into(), path: "build/font.2bpp.lz".into(), format: ExportFormat::Raw, dedupe: true, compression: Compression::Lz77 }
+        ]
+    }
+}
+
+#[test]
+fn json_roundtrip()
+{
+    let project = project();
+    assert_eq!(Project::from_json(&project.to_json()).unwrap(), project);
+    assert_eq!(project.document("font").unwrap().palette.as_deref(), Some("gray"));
+    assert_eq!(project.document("font").unwrap().animations[0].frames[1].first_tile, 300);
+    assert!(project.palette("color").is_none());
+
+    // missing lists and export options fall back to their defaults
+    let minimal = Project::from_json(r#"{ "exports": [{ "document": "font", "path": "font.chr", "format": "Chr" }] }"#).unwrap();
+    assert!(minimal.documents.is_empty());
+    let document = Project::from_json(r#"{ "documents": [{ "name": "font", "path": "font.tl" }] }"#).unwrap();
+    assert!(document.documents[0].animations.is_empty());
+    assert_eq!((minimal.exports[0].dedupe, minimal.exports[0].compression), (false, Compression::None));
+    assert!(Project::from_json("{ \"documents\": 3 }").is_err());
+}
+
+#[test]
+fn builds_every_export()
+{
+    let mut storage = MemoryStorage::default();
+    storage.write("game/art/font.tl", &tl::to_tl(&sheet()).unwrap()).unwrap();
+
+    let loaded = Project::from_json(&project().to_json()).unwrap();
+    let written = loaded.build(&mut storage, "game/game.json").unwrap();
+    assert_eq!(written, [("game/font.2bpp".to_owned(), 4 * 16), ("game/build/font.2bpp.lz".to_owned(), written[1].1)]);
+    assert_eq!(storage.read("game/font.2bpp").unwrap(), sheet().export());
+
+    let packed = storage.read("game/build/font.2bpp.lz").unwrap();
+    assert_eq!(packed.len(), written[1].1);
+    let unpacked = Compression::Lz77.decompress(&packed).unwrap();
+    assert!(unpacked.len() <= 4 * 16 && unpacked.len() % 16 == 0, "deduplicated tiles");
+
+    let mut broken = project();
+    broken.exports[0].document = "missing".into();
+    assert!(broken.build(&mut storage, "game/game.json").is_err());
+    assert!(project().build(&mut MemoryStorage::default(), "game/game.json").is_err(), "documents are read from the storage");
+}
+
+#[test]
+fn archive_roundtrip()
+{
+    let mut storage = MemoryStorage::default();
+    let font = tl::to_tl(&sheet()).unwrap();
+    storage.write("game/art/font.tl", &font).unwrap();
+
+    let archive = project().export_archive(&storage, "game/game.json").unwrap();
+    let mut imported = MemoryStorage::default();
+    let (path, loaded) = project::import_archive(&mut imported, "copy", &archive).unwrap();
+    assert_eq!((path.as_str(), &loaded), ("copy/game.json", &project()));
+    assert_eq!(imported.list(), ["copy/art/font.tl", "copy/game.json"]);
+    assert_eq!(imported.read("copy/art/font.tl").unwrap(), font);
+
+    // documents have to be in storage and inside the project directory
+    assert!(project().export_archive(&MemoryStorage::default(), "game/game.json").is_err());
+    let mut outside = project();
+    outside.documents[0].path = "../font.tl".into();
+    assert!(outside.export_archive(&storage, "game/game.json").is_err());
+
+    let escaping = br#"{ "project": "game.json", "files": { "game.json": "e30", "../evil.tl": "" } }"#;
+    assert!(project::import_archive(&mut imported, "copy", escaping).is_err());
+    assert!(project::import_archive(&mut imported, "copy", br#"{ "project": "game.json", "files": {} }"#).is_err());
+    assert_eq!(imported.list().len(), 2, "nothing written from broken archives");
+}
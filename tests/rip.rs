@@ -0,0 +1,79 @@
+use rzrtiles::codec::TileMode;
+use rzrtiles::format::TileFormat;
+use rzrtiles::rip::TileRipper;
+use rzrtiles::sheet::TileSheet;
+
+/// 10 distinct 2bpp tiles
+fn tiles() -> Vec<u8>
+{
+    (0..10u8).flat_map(|t| (0..16u8).map(move |i| t.wrapping_mul(29) ^ i)).collect()
+}
+
+/// 3 bytes before the tiles and 5 bytes of a partial tile after them
+fn ripper() -> TileRipper
+{
+    let mut data = vec![0xaa, 0xbb, 0xcc];
+    data.extend(tiles());
+    data.extend([1, 2, 3, 4, 5]);
+    let mut ripper = TileRipper::new(data);
+    ripper.offset = 3;
+    ripper.tiles_per_row = 4;
+    ripper
+}
+
+#[test]
+fn counts_whole_tiles_from_the_offset()
+{
+    let mut ripper = ripper();
+    assert_eq!((ripper.tile_count(), ripper.rows()), (10, 3));
+    assert_eq!(ripper.tile_address(0), 3);
+    assert_eq!(ripper.tile_address(2), 3 + 2 * 16);
+
+    // the 8 bytes after the last whole tile do not make one
+    ripper.offset = 0;
+    assert_eq!(ripper.tile_count(), 10);
+    ripper.offset = 12;
+    assert_eq!(ripper.tile_count(), 9);
+
+    ripper.mode = TileMode::Y16;
+    assert_eq!((ripper.tile_count(), ripper.rows()), (4, 1));
+    assert_eq!(ripper.tile_address(1), 12 + 32);
+
+    ripper.offset = ripper.data.len() + 1;
+    assert_eq!((ripper.tile_count(), ripper.rows()), (0, 0));
+    assert!(ripper.tiles(0, 4).is_empty());
+}
+
+#[test]
+fn extracts_at_unaligned_offsets()
+{
+    let ripper = ripper();
+    let tiles = tiles();
+    assert_eq!(ripper.tiles(1, 3), &tiles[16..64]);
+    assert_eq!(ripper.extract(1, 3), TileSheet::from_tiles(&tiles[16..64], 4, TileMode::Y8));
+    assert_eq!(ripper.extract(0, 10), TileSheet::from_tiles(&tiles, 4, TileMode::Y8));
+
+    let mut nes = ripper.clone();
+    nes.format = TileFormat::Nes;
+    assert_eq!(nes.extract(2, 2), TileSheet::from_tiles_as(TileFormat::Nes, &tiles[32..64], 4, TileMode::Y8));
+}
+
+#[test]
+fn partial_last_tile_is_left_out()
+{
+    let ripper = ripper();
+    let tiles = tiles();
+
+    // the last row holds tiles 8 and 9, the data runs on into the partial tile
+    // which is dropped when decoding
+    assert_eq!(ripper.tiles(8, 4), &ripper.data[3 + 128..]);
+    assert_eq!(ripper.tiles(8, 4).len(), 32 + 5);
+    let last = ripper.rows_sheet(2, 1);
+    assert_eq!(last, TileSheet::from_tiles(&tiles[128..], 4, TileMode::Y8));
+    assert_eq!((last.width, last.height), (16, 8));
+
+    let all = ripper.rows_sheet(0, 5);
+    assert_eq!(all, ripper.extract(0, 10));
+    assert_eq!((all.width, all.height), (32, 24));
+    assert_eq!(ripper.rows_sheet(1, 1), ripper.extract(4, 4));
+}
@@ -0,0 +1,170 @@
+use rzrtiles::codec::TileMode;
+use rzrtiles::rom::{self, RomError, DEFAULT_BGP, NINTENDO_LOGO, ROM_SIZE};
+use rzrtiles::sheet::TileSheet;
+use rzrtiles::tilemap::{MapCell, Tilemap};
+
+/// just enough of the SM83 to run the stub, LY always reads as in VBlank
+struct Cpu
+{
+    mem: Vec<u8>,
+    pc: u16,
+    sp: u16,
+    a: u8,
+    bc: u16,
+    de: u16,
+    hl: u16,
+    zero: bool,
+    carry: bool
+}
+
+impl Cpu
+{
+    fn new(rom: &[u8]) -> Self
+    {
+        let mut mem = vec![0u8; 0x10000];
+        mem[..rom.len()].copy_from_slice(rom);
+        mem[0xff44] = 144;
+        Cpu { mem, pc: 0x100, sp: 0, a: 0, bc: 0, de: 0, hl: 0, zero: false, carry: false }
+    }
+
+    fn byte(&mut self) -> u8
+    {
+        let b = self.mem[self.pc as usize];
+        self.pc += 1;
+        b
+    }
+
+    fn word(&mut self) -> u16
+    {
+        u16::from_le_bytes([self.byte(), self.byte()])
+    }
+
+    fn jr(&mut self, taken: bool)
+    {
+        let e = self.byte() as i8;
+        if taken {
+            self.pc = self.pc.wrapping_add(e as u16);
+        }
+    }
+
+    /// runs until the stub halts
+    fn run(&mut self)
+    {
+        for _ in 0..1_000_000 {
+            match self.byte() {
+                0x00 | 0xf3 => {}
+                0x76 => return,
+                0xc3 => self.pc = self.word(),
+                0x31 => self.sp = self.word(),
+                0x21 => self.hl = self.word(),
+                0x11 => self.de = self.word(),
+                0x01 => self.bc = self.word(),
+                0x3e => self.a = self.byte(),
+                0xf0 => { let n = self.byte(); self.a = self.mem[0xff00 + n as usize]; }
+                0xe0 => { let n = self.byte(); self.mem[0xff00 + n as usize] = self.a; }
+                0xfe => { let n = self.byte(); self.zero = self.a == n; self.carry = self.a < n; }
+                0xaf => { self.a = 0; self.zero = true; self.carry = false; }
+                0xb1 => { self.a |= self.bc as u8; self.zero = self.a == 0; self.carry = false; }
+                0x78 => self.a = (self.bc >> 8) as u8,
+                0x2a => { self.a = self.mem[self.hl as usize]; self.hl += 1; }
+                0x12 => self.mem[self.de as usize] = self.a,
+                0x13 => self.de += 1,
+                0x0b => self.bc -= 1,
+                0x18 => self.jr(true),
+                0x20 => { let taken = !self.zero; self.jr(taken); }
+                0x38 => { let taken = self.carry; self.jr(taken); }
+                0xcd => {
+                    let target = self.word();
+                    self.sp -= 2;
+                    self.mem[self.sp as usize..][..2].copy_from_slice(&self.pc.to_le_bytes());
+                    self.pc = target;
+                }
+                0xc9 => {
+                    self.pc = u16::from_le_bytes([self.mem[self.sp as usize], self.mem[self.sp as usize + 1]]);
+                    self.sp += 2;
+                }
+                op => panic!("unexpected opcode {:02X} at {:04X}", op, self.pc - 1)
+            }
+        }
+        panic!("stub did not halt");
+    }
+}
+
+fn sheet() -> TileSheet
+{
+    let mut sheet = TileSheet::new(24, 16, TileMode::Y8);
+    for (i, p) in sheet.pixels.iter_mut().enumerate() {
+        *p = (i % 7 % 4) as u8;
+    }
+    sheet
+}
+
+#[test]
+fn header_passes_boot_checks()
+{
+    let data = rom::sheet_rom(&sheet(), DEFAULT_BGP).unwrap();
+    assert_eq!(data.len(), ROM_SIZE);
+    assert_eq!(data[0x104..0x134], NINTENDO_LOGO);
+
+    // the boot ROM's check: sum of $0134-$014D plus 25 is zero
+    let sum = data[0x134..=0x14d].iter().fold(25u8, |x, b| x.wrapping_add(*b));
+    assert_eq!(sum, 0);
+    assert_eq!(u16::from_be_bytes([data[0x14e], data[0x14f]]), rom::global_checksum(&data));
+}
+
+#[test]
+fn stub_shows_sheet()
+{
+    let sheet = sheet();
+    let data = rom::sheet_rom(&sheet, 0b00_01_10_11).unwrap();
+    let mut cpu = Cpu::new(&data);
+    cpu.run();
+
+    assert_eq!(cpu.mem[0xff40], 0x91);
+    assert_eq!(cpu.mem[0xff47], 0b00_01_10_11);
+
+    // decode what the BG shows from VRAM and compare with the sheet
+    let vram = TileSheet::from_tiles(&cpu.mem[0x8000..0x9000], 16, TileMode::Y8);
+    let mut map = Tilemap::new(32, 32);
+    for (i, cell) in map.cells.iter_mut().enumerate() {
+        *cell = MapCell { tile: cpu.mem[0x9800 + i] as u16, ..Default::default() };
+    }
+    let screen = map.render(&vram);
+    for y in 0..sheet.height {
+        for x in 0..sheet.width {
+            assert_eq!(screen.get(x, y), sheet.get(x, y), "pixel {} {}", x, y);
+        }
+    }
+    assert!(screen.get(sheet.width, 0) == 0 && screen.get(0, sheet.height) == 0);
+}
+
+#[test]
+fn flipped_cells_and_duplicates()
+{
+    let sheet = sheet();
+    let mut map = Tilemap::new(2, 1);
+    map.set(0, 0, MapCell { tile: 1, ..Default::default() });
+    map.set(1, 0, MapCell { tile: 1, flip_x: true, flip_y: true, ..Default::default() });
+
+    let data = rom::test_rom(&sheet, &map, DEFAULT_BGP).unwrap();
+    let mut cpu = Cpu::new(&data);
+    cpu.run();
+    assert_eq!(cpu.mem[0x9800..0x9803], [1, 2, 0]);
+
+    let vram = TileSheet::from_tiles(&cpu.mem[0x8000..0x9000], 16, TileMode::Y8);
+    assert_eq!(map.render(&sheet), Tilemap { width: 2, height: 1, cells: vec![
+        MapCell { tile: 1, ..Default::default() },
+        MapCell { tile: 2, ..Default::default() }
+    ]}.render(&vram));
+
+    // 288 different tiles do not fit the $8000 block
+    let mut many = TileSheet::new(32 * 8, 9 * 8, TileMode::Y8);
+    for t in 0..many.tile_count() {
+        let (tx, ty) = many.tile_origin(t).unwrap();
+        for x in 0..8 {
+            many.set(tx + x, ty, (t >> x & 1) as u8);
+        }
+        many.set(tx, ty + 1, (t >> 8) as u8 + 1);
+    }
+    assert_eq!(rom::sheet_rom(&many, DEFAULT_BGP), Err(RomError::TooManyTiles));
+}
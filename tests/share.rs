@@ -0,0 +1,75 @@
+use rzrtiles::codec::TileMode;
+use rzrtiles::format::TileFormat;
+use rzrtiles::share::{self, ShareError};
+use rzrtiles::sheet::TileSheet;
+
+fn sheet(width: u32, height: u32, mode: TileMode, colors: u32) -> TileSheet
+{
+    let mut sheet = TileSheet::new(width, height, mode);
+    for y in 0..height {
+        for x in 0..width {
+            sheet.set(x, y, ((x * 3 + y / 2) % colors) as u8);
+        }
+    }
+    sheet
+}
+
+#[test]
+fn base64url_roundtrip()
+{
+    let data: Vec<u8> = (0..=255).rev().collect();
+    for len in 0..10 {
+        let encoded = share::base64url_encode(&data[..len]);
+        assert_eq!(encoded.len(), (len * 4 + 2) / 3);
+        assert!(encoded.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+        assert_eq!(share::base64url_decode(&encoded).unwrap(), &data[..len]);
+    }
+    assert_eq!(share::base64url_encode(b"\xfb\xff"), "-_8");
+    assert_eq!(share::base64url_decode("ab+c"), Err(ShareError::Base64(2)));
+}
+
+#[test]
+fn links_roundtrip()
+{
+    let palette = [[255, 255, 255], [170, 170, 170], [85, 85, 85], [0, 0, 0], [255, 0, 0]];
+    for (sheet, format) in [(sheet(8, 8, TileMode::Y8, 4), TileFormat::Gb), (sheet(32, 48, TileMode::Y16, 16), TileFormat::Snes)] {
+        let link = share::link("https://example.com/rzrTiles/#old", &sheet, format, &palette).unwrap();
+        assert!(link.starts_with("https://example.com/rzrTiles/#tiles="));
+
+        let shared = share::from_link(&link).unwrap().unwrap();
+        assert_eq!((&shared.sheet, shared.format), (&sheet, format));
+        // 2bpp formats only share the colors they can show
+        assert_eq!(shared.palette, &palette[..std::cmp::min(palette.len(), format.colors())]);
+    }
+
+    // a single 8x8 tile fits in a short link
+    let tile = share::encode(&sheet(8, 8, TileMode::Y8, 4), TileFormat::Gb, &palette).unwrap();
+    assert!(tile.len() < 64, "{}", tile);
+}
+
+#[test]
+fn refuses_long_links()
+{
+    // noise doesn't compress
+    let mut sheet = TileSheet::new(128, 128, TileMode::Y8);
+    let mut seed = 1u32;
+    for p in &mut sheet.pixels {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        *p = (seed >> 16) as u8 & 15;
+    }
+    match share::link("https://example.com/", &sheet, TileFormat::Snes, &[]) {
+        Err(ShareError::TooLong(len)) => assert!(len > share::MAX_LINK_LEN),
+        other => panic!("{:?}", other.map(|link| link.len()))
+    }
+}
+
+#[test]
+fn rejects_broken_links()
+{
+    assert!(share::from_link("https://example.com/").is_none());
+    assert_eq!(share::decode("").unwrap_err(), ShareError::Truncated);
+
+    let encoded = share::encode(&sheet(16, 16, TileMode::Y8, 4), TileFormat::Nes, &[[0; 3]; 4]).unwrap();
+    assert!(share::decode(&encoded[..encoded.len() / 2]).is_err());
+    assert!(matches!(share::decode("AQ"), Err(ShareError::Truncated)));
+}
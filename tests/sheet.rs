@@ -0,0 +1,19 @@
+use rzrtiles::sheet::{quantize_luma, TileSheet};
+use rzrtiles::codec::TileMode;
+use image::{GrayImage, Luma};
+
+#[test]
+fn light_pixels_map_to_index_0()
+{
+    assert_eq!([255, 192, 128, 64, 0].map(quantize_luma), [0, 0, 1, 2, 3]);
+}
+
+#[test]
+fn from_luma_crops_to_whole_tiles()
+{
+    let img = GrayImage::from_fn(9, 10, |x, _| if x < 4 { Luma([255]) } else { Luma([0]) });
+    let sheet = TileSheet::from_luma(&img, TileMode::Y8);
+    assert_eq!((sheet.width, sheet.height), (8, 8));
+    assert_eq!(sheet.get(0, 0), 0);
+    assert_eq!(sheet.get(7, 7), 3);
+}
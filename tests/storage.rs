@@ -0,0 +1,39 @@
+use rzrtiles::storage::{DocumentStorage, FileStorage, MemoryStorage};
+
+/// writes, lists, reads back and removes a few files
+fn roundtrip(storage: &mut dyn DocumentStorage)
+{
+    storage.write("b.tl", b"second").unwrap();
+    storage.write("a.tl", b"first").unwrap();
+    storage.write("a.tl", b"replaced").unwrap();
+    assert_eq!(storage.list(), ["a.tl", "b.tl"]);
+    assert_eq!(storage.read("a.tl").unwrap(), b"replaced");
+
+    storage.remove("b.tl").unwrap();
+    assert_eq!(storage.list(), ["a.tl"]);
+    assert!(storage.read("b.tl").is_err());
+    assert!(storage.remove("b.tl").is_err());
+}
+
+#[test]
+fn memory_storage()
+{
+    let mut storage = MemoryStorage::default();
+    roundtrip(&mut storage);
+    storage.write("dir/c.tl", b"nested").unwrap();
+    assert_eq!(storage.list(), ["a.tl", "dir/c.tl"]);
+}
+
+#[test]
+fn file_storage()
+{
+    let dir = std::env::temp_dir().join(format!("rzrtiles_storage_{}", std::process::id()));
+    let mut storage = FileStorage::new(&dir);
+    roundtrip(&mut storage);
+
+    // parent directories are created, only the files in the root are listed
+    storage.write("dir/c.tl", b"nested").unwrap();
+    assert_eq!(std::fs::read(dir.join("dir/c.tl")).unwrap(), b"nested");
+    assert_eq!(storage.list(), ["a.tl"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,169 @@
+use rzrtiles::codec::TileMode;
+use rzrtiles::sheet::TileSheet;
+use rzrtiles::tiled::{self, TiledError, TilesetSource, FLIP_DIAGONAL, FLIP_X, FLIP_Y};
+use rzrtiles::tilemap::{MapCell, Tilemap};
+
+/// 4 tiles where tile 2 repeats tile 0
+fn sheet() -> TileSheet
+{
+    let mut sheet = TileSheet::new(32, 8, TileMode::Y8);
+    for y in 0..8 {
+        for x in 0..32 {
+            let tile = x / 8 % 2;
+            sheet.set(x, y, ((x + y * (tile + 1)) % 4) as u8);
+        }
+    }
+    sheet.set(31, 7, 3);
+    sheet
+}
+
+fn flipped_map() -> Tilemap
+{
+    let mut map = Tilemap::new(3, 2);
+    map.set(0, 0, MapCell { tile: 1, flip_x: true, ..Default::default() });
+    map.set(1, 0, MapCell { tile: 2, flip_y: true, ..Default::default() });
+    map.set(2, 1, MapCell { tile: 3, flip_x: true, flip_y: true, ..Default::default() });
+    map
+}
+
+#[test]
+fn gid_flags()
+{
+    let cell = MapCell { tile: 5, flip_x: true, flip_y: true, ..Default::default() };
+    assert_eq!(tiled::to_gid(cell, 1), 6 | FLIP_X | FLIP_Y);
+    assert_eq!(tiled::from_gid(6 | FLIP_X | FLIP_Y, 1), Some(cell));
+    assert_eq!(tiled::from_gid(0, 1), Some(MapCell::default()));
+    assert_eq!(tiled::from_gid(6 | FLIP_DIAGONAL, 1), None);
+}
+
+#[test]
+fn tmx_roundtrip()
+{
+    let sheet = sheet();
+    let map = flipped_map();
+    let atlas = tiled::atlas(&sheet);
+
+    let tileset = tiled::parse_tsx(&tiled::tsx("level", "level.png", &atlas)).unwrap();
+    assert_eq!((tileset.image.as_str(), tileset.columns, tileset.tile_count), ("level.png", 4, 4));
+
+    let parsed = tiled::parse_tmx(&tiled::tmx(&map, "level.tsx").unwrap()).unwrap();
+    assert_eq!(parsed.tileset, TilesetSource::External("level.tsx".to_owned()));
+    assert_eq!((parsed.width, parsed.height, parsed.firstgid), (3, 2, 1));
+
+    // tile 2 duplicates tile 0, so the rebuilt set has 3 tiles and tile 3 becomes 2
+    let (tileset, rebuilt) = tiled::rebuild(&parsed, &atlas).unwrap();
+    assert_eq!(tileset.tile_count(), 3);
+    let tiles: Vec<u16> = rebuilt.cells.iter().map(|c| c.tile).collect();
+    assert_eq!(tiles, [1, 0, 0, 0, 0, 2]);
+    assert_eq!(rebuilt.cells.iter().map(|c| (c.flip_x, c.flip_y)).collect::<Vec<_>>(),
+        map.cells.iter().map(|c| (c.flip_x, c.flip_y)).collect::<Vec<_>>());
+    assert_eq!(rebuilt.render(&tileset), map.render(&sheet));
+}
+
+#[test]
+fn tmj_roundtrip()
+{
+    let sheet = sheet();
+    let map = flipped_map();
+    let parsed = tiled::parse_tmj(&tiled::tmj(&map, "level.tsx").unwrap()).unwrap();
+    assert_eq!(parsed, tiled::parse_tmx(&tiled::tmx(&map, "level.tsx").unwrap()).unwrap());
+
+    let (tileset, rebuilt) = tiled::rebuild(&parsed, &tiled::atlas(&sheet)).unwrap();
+    assert_eq!(rebuilt.render(&tileset), map.render(&sheet));
+}
+
+#[test]
+fn embedded_tileset_and_xml_layer()
+{
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="1" tilewidth="8" tileheight="8" infinite="0">
+ <tileset firstgid="10" name="t" tilewidth="8" tileheight="8" tilecount="4" columns="4">
+  <image source="t.png" width="32" height="8"/>
+ </tileset>
+ <layer id="1" name="Tiles" width="2" height="1">
+  <data>
+   <tile gid="2147483661"/>
+   <tile/>
+  </data>
+ </layer>
+</map>"#;
+    let parsed = tiled::parse_tmx(xml).unwrap();
+    assert!(matches!(parsed.tileset, TilesetSource::Embedded(ref t) if t.image == "t.png" && t.columns == 4));
+    assert_eq!(parsed.gids, [13 | FLIP_X, 0]);
+
+    let (_, map) = tiled::rebuild(&parsed, &tiled::atlas(&sheet())).unwrap();
+    assert_eq!(map.get(0, 0), MapCell { tile: 2, flip_x: true, ..Default::default() });
+}
+
+#[test]
+fn unsupported_maps()
+{
+    let map = flipped_map();
+    let tmx = tiled::tmx(&map, "level.tsx").unwrap();
+
+    let big_tiles = tmx.replace("tilewidth=\"8\"", "tilewidth=\"16\"");
+    assert_eq!(tiled::parse_tmx(&big_tiles), Err(TiledError::TileSize { width: 16, height: 8 }));
+
+    let base64 = tmx.replace("encoding=\"csv\"", "encoding=\"base64\"");
+    assert!(matches!(tiled::parse_tmx(&base64), Err(TiledError::Unsupported(_))));
+
+    let mut rotated = tiled::parse_tmx(&tmx).unwrap();
+    rotated.gids[4] |= FLIP_DIAGONAL;
+    assert_eq!(tiled::rebuild(&rotated, &tiled::atlas(&sheet())), Err(TiledError::Rotated { x: 1, y: 1 }));
+
+    let mut out_of_range = tiled::parse_tmx(&tmx).unwrap();
+    out_of_range.gids[0] = 9;
+    assert_eq!(tiled::rebuild(&out_of_range, &tiled::atlas(&sheet())), Err(TiledError::TileOutOfRange { x: 0, y: 0, gid: 9 }));
+    // wraps to tile 1 when cast to u16 before the range check
+    out_of_range.gids[0] = 0x1_0002;
+    assert_eq!(tiled::from_gid(0x1_0002, 1), None);
+    assert_eq!(tiled::rebuild(&out_of_range, &tiled::atlas(&sheet())), Err(TiledError::TileOutOfRange { x: 0, y: 0, gid: 0x1_0002 }));
+
+    assert_eq!(tiled::tmx(&Tilemap::new(0, 3), "level.tsx"), Err(TiledError::Empty { width: 0, height: 3 }));
+    assert!(tiled::tmj(&Tilemap::new(3, 0), "level.tsx").is_err());
+    let empty = tmx.replacen("width=\"3\"", "width=\"0\"", 2);
+    assert_eq!(tiled::parse_tmx(&empty), Err(TiledError::Empty { width: 0, height: 2 }));
+}
+
+#[test]
+fn tmj_numbers_out_of_range()
+{
+    let tmj: serde_json::Value = serde_json::from_str(&tiled::tmj(&flipped_map(), "level.tsx").unwrap()).unwrap();
+
+    // 2^32 + 8 used to wrap to 8
+    let mut big = tmj.clone();
+    big["tilewidth"] = serde_json::json!(0x1_0000_0008u64);
+    assert_eq!(tiled::parse_tmj(&big.to_string()), Err(TiledError::OutOfRange("tilewidth")));
+
+    let mut negative = tmj.clone();
+    negative["layers"][0]["width"] = serde_json::json!(-3);
+    assert_eq!(tiled::parse_tmj(&negative.to_string()), Err(TiledError::OutOfRange("width")));
+
+    let mut gid = tmj.clone();
+    gid["layers"][0]["data"][0] = serde_json::json!(0x1_0000_0001u64);
+    assert_eq!(tiled::parse_tmj(&gid.to_string()), Err(TiledError::OutOfRange("data")));
+
+    let mut missing = tmj;
+    missing["layers"][0]["data"][0] = serde_json::json!("1");
+    assert_eq!(tiled::parse_tmj(&missing.to_string()), Err(TiledError::Missing("data")));
+}
+
+#[test]
+fn files_roundtrip()
+{
+    let dir = std::env::temp_dir().join(format!("rzrtiles_tiled_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let sheet = sheet();
+    let map = flipped_map();
+
+    for name in ["level.tmx", "level.tmj"] {
+        let path = dir.join(name);
+        tiled::export(&sheet, &map, &path).unwrap();
+        assert!(dir.join("level.tsx").exists() && dir.join("level.png").exists());
+
+        let (tileset, imported) = tiled::import(&path).unwrap();
+        assert_eq!(tileset.tile_count(), 3);
+        assert_eq!(imported.render(&tileset), map.render(&sheet));
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
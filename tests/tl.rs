@@ -0,0 +1,45 @@
+use rzrtiles::codec::{gb_tiles_to_pixels, pixels_to_gb_tiles, TileMode};
+use rzrtiles::format::TileFormat;
+use rzrtiles::sheet::TileSheet;
+use rzrtiles::tl::{self, TlHeader, TL_HEADER_SIZE, TL_V1_HEADER_SIZE, TL_VERSION};
+
+#[test]
+fn y16_header_counts_8_pixel_rows()
+{
+    let header = TlHeader::parse(&[0x72, 0x54, 0x69, 0x6c, 1, 16, 1, 2]).unwrap();
+    assert_eq!((header.tiles_x(), header.tiles_y(), header.tile_count()), (1, 1, 1));
+    assert_eq!(header.data_size(), 32);
+}
+
+#[test]
+fn y16_tiles_round_trip()
+{
+    let (w, h) = (16u32, 32u32);
+    let pixels: Vec<u8> = (0..w*h).map(|i| {
+        let (x, y) = (i % w, i / w);
+        ((x/8 + 2*(y/16)) % 4) as u8
+    }).collect();
+
+    let tiles = pixels_to_gb_tiles(&pixels, w, h, TileMode::Y16);
+    let decoded = gb_tiles_to_pixels(&tiles, w/8, h/16, TileMode::Y16);
+    assert_eq!(decoded, pixels);
+    assert_eq!(decoded[(16*w) as usize], 2);
+}
+
+#[test]
+fn gb_sheets_are_written_as_version_1()
+{
+    let mut sheet = TileSheet::new(16, 16, TileMode::Y16);
+    sheet.set(9, 12, 3);
+
+    let data = tl::to_tl(&sheet).unwrap();
+    assert_eq!(data[4], 1);
+    assert_eq!(data.len(), TL_V1_HEADER_SIZE + 2 * 32);
+    assert_eq!(TlHeader::parse(&data).unwrap().format, TileFormat::Gb);
+    assert_eq!(tl::read_tl(&data).unwrap(), (sheet.clone(), TileFormat::Gb));
+
+    let data = tl::to_tl_as(&sheet, TileFormat::Nes).unwrap();
+    assert_eq!((data[4], data[8]), (TL_VERSION, TileFormat::Nes as u8));
+    assert_eq!(data.len(), TL_HEADER_SIZE + 2 * 32);
+    assert_eq!(tl::read_tl(&data).unwrap(), (sheet, TileFormat::Nes));
+}
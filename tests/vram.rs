@@ -0,0 +1,91 @@
+use rzrtiles::vram::{self, TileAddressing, VramError, BANK_TILES, VRAM_BANK_SIZE};
+
+/// VRAM with tile n filled with its own index so tiles can be told apart after decoding
+fn numbered_vram(banks: usize) -> Vec<u8>
+{
+    let mut data = vec![0u8; banks * VRAM_BANK_SIZE];
+    for bank in 0..banks {
+        for t in 0..384 {
+            let tile = &mut data[bank * VRAM_BANK_SIZE + t * 16..][..16];
+            tile.fill((t + bank) as u8);
+        }
+    }
+    data
+}
+
+#[test]
+fn signed_addressing_reads_9000_block()
+{
+    let mut data = numbered_vram(1);
+    data[0x1800] = 0x00; // $9000
+    data[0x1801] = 0x7f; // $97F0
+    data[0x1802] = 0x80; // $8800
+    data[0x1c00] = 0xff; // $8FF0
+
+    let signed = vram::read_vram(&data, TileAddressing::Signed).unwrap();
+    let tiles: Vec<u16> = signed.maps[0].cells[..3].iter().map(|c| c.tile).collect();
+    assert_eq!(tiles, [256, 383, 128]);
+    assert_eq!(signed.maps[1].get(0, 0).tile, 255);
+
+    let unsigned = vram::read_vram(&data, TileAddressing::Unsigned).unwrap();
+    assert_eq!(unsigned.maps[0].get(1, 0).tile, 0x7f);
+    assert_eq!(unsigned.tileset.tile_count(), 384);
+}
+
+#[test]
+fn cgb_attributes()
+{
+    let mut data = numbered_vram(2);
+    data[0x1800] = 5;
+    data[VRAM_BANK_SIZE + 0x1800] = 0x80 | 0x20 | 0x08 | 0x03;
+
+    let imported = vram::read_vram(&data, TileAddressing::Unsigned).unwrap();
+    assert_eq!(imported.tileset.tile_count(), 2 * BANK_TILES as u32);
+
+    let cell = imported.maps[0].get(0, 0);
+    assert_eq!(cell.tile, BANK_TILES + 5);
+    assert!(cell.priority && cell.flip_x && !cell.flip_y);
+    assert_eq!(cell.palette, 3);
+
+    // tile 5 of bank 1 is filled with 0x06, rows are 0 0 0 0 0 3 3 0 before flipping
+    let rendered = imported.maps[0].render(&imported.tileset);
+    let row: Vec<u8> = (0..8).map(|x| rendered.get(x, 0)).collect();
+    assert_eq!(row, [0, 3, 3, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn bess_save_state()
+{
+    let vram_data = numbered_vram(1);
+    let mut state = vec![0xaa; 100]; // stands in for the emulator's own state
+    let vram_offset = state.len();
+    state.extend_from_slice(&vram_data);
+
+    let mut core = vec![0u8; 0xd0];
+    core[0x18 + 0x40] = 0x80 | 0x08; // LCDC: $8800 tiles, $9C00 map
+    core[0xa0..0xa4].copy_from_slice(&(vram_data.len() as u32).to_le_bytes());
+    core[0xa4..0xa8].copy_from_slice(&(vram_offset as u32).to_le_bytes());
+
+    let first_block = state.len();
+    state.extend_from_slice(b"NAME");
+    state.extend_from_slice(&7u32.to_le_bytes());
+    state.extend_from_slice(b"SameBoy");
+    state.extend_from_slice(b"CORE");
+    state.extend_from_slice(&(core.len() as u32).to_le_bytes());
+    state.extend_from_slice(&core);
+    state.extend_from_slice(b"END ");
+    state.extend_from_slice(&0u32.to_le_bytes());
+    state.extend_from_slice(&(first_block as u32).to_le_bytes());
+    state.extend_from_slice(b"BESS");
+
+    let imported = vram::import(&state, TileAddressing::Unsigned).unwrap();
+    assert_eq!(imported.lcdc, Some(0x88));
+    assert_eq!(imported.maps[0].get(0, 0).tile, 256);
+
+    assert_eq!(vram::import(&state[..state.len() - 1], TileAddressing::Unsigned), Err(VramError::BadSaveState));
+
+    // a block length running past the end, or past usize::MAX on 32 bit targets
+    let mut huge = state.clone();
+    huge[first_block + 4..first_block + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(vram::import(&huge, TileAddressing::Unsigned), Err(VramError::BadSaveState));
+}